@@ -1,25 +1,70 @@
 
 #[cfg(feature = "std")]
-use std::{fs::File, io::{BufReader, Write}, path::PathBuf};
+use std::{fs::File, io::{BufReader, BufWriter, Write}, path::{Path, PathBuf}};
 #[cfg(feature = "std")]
-use clap::{Parser, ValueHint};
+use clap::{Parser, Subcommand, ValueHint};
 #[cfg(feature = "std")]
 use image::{ImageDecoder, ColorType, GenericImageView};
 #[cfg(feature = "std")]
-use qoi::{ColorSpace, QoiDecoder, QoiEncoder};
+use qoi::{ColorSpace, DecodeMode, FormatVersion, QoiDecoder, QoiEncoder};
 
 #[cfg(feature = "std")]
 #[derive(Parser)]
 #[clap(name = "qoiconv", author = "John Peel <john@dgby.org>")]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Opts {
-    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
-    input: PathBuf,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath, required = true)]
+    input: Option<PathBuf>,
     #[clap(short, long)]
     width: Option<u32>,
     #[clap(short, long)]
     height: Option<u32>,
-    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
-    output: PathBuf
+    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath, required = true)]
+    output: Option<PathBuf>
+}
+
+#[cfg(feature = "std")]
+#[derive(Subcommand)]
+enum Command {
+    /// Rewrite draft .qoi files as QOI 1.0 in place
+    Migrate {
+        #[clap(parse(from_os_str), value_hint = ValueHint::FilePath, required = true)]
+        files: Vec<PathBuf>
+    }
+}
+
+#[cfg(feature = "std")]
+fn migrate(path: &Path) -> Result<Option<(u64, u64)>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let metadata = std::fs::metadata(path)?;
+    let before = metadata.len();
+    // Strict, so a truncated or misdetected file fails here instead of replacing the original.
+    let mut decoder = QoiDecoder::builder()
+        .mode(DecodeMode::Strict)
+        .build(BufReader::new(File::open(path)?))?;
+    if decoder.format_version() == FormatVersion::V1 {
+        return Ok(None);
+    }
+
+    let mut temp_name = path.file_name().ok_or("INPUT is not a file.")?.to_owned();
+    temp_name.push(".migrate");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut output = BufWriter::new(File::create(&temp_path)?);
+        qoi::transcode(&mut decoder, &mut output, FormatVersion::V1)?;
+        let output = output.into_inner().map_err(|e| e.into_error())?;
+        output.set_permissions(metadata.permissions())?;
+        output.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    Ok(Some((before, std::fs::metadata(path)?.len())))
 }
 
 #[cfg(feature = "std")]
@@ -30,43 +75,66 @@ fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
 
     let opts: Opts = Opts::parse();
 
-    if !opts.input.exists() {
+    if let Some(Command::Migrate { files }) = opts.command {
+        let mut failed = 0;
+        for path in &files {
+            match migrate(path) {
+                Ok(Some((before, after))) => println!(
+                    "{}: {} -> {} bytes ({:+.2}%)",
+                    path.display(), before, after, (after as f64 - before as f64) / before as f64 * 100.0
+                ),
+                Ok(None) => println!("{}: already QOI 1.0", path.display()),
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} files could not be migrated.", failed, files.len()).into());
+        }
+        return Ok(());
+    }
+
+    let (input, output) = (opts.input.unwrap(), opts.output.unwrap());
+    if !input.exists() {
         return Err("INPUT file not found.".into());
     }
 
-    match (opts.input.extension(), opts.output.extension()) {
+    match (input.extension(), output.extension()) {
         (Some(input_ext), Some(output_ext)) if input_ext == "qoi" && output_ext == "raw" => {
-            let decoder = QoiDecoder::new(BufReader::new(File::open(opts.input)?))?;
+            let decoder = QoiDecoder::new(BufReader::new(File::open(input)?))?;
             let mut buf: Vec<u8> = vec![0; decoder.total_bytes() as usize];
             decoder.read_image(&mut buf)?;
 
-            std::fs::File::create(opts.output)?.write_all(&buf)?;
+            std::fs::File::create(output)?.write_all(&buf)?;
         },
         (Some(ext), _) if ext == "qoi" => {
-            let decoder = QoiDecoder::new(BufReader::new(File::open(opts.input)?))?;
+            let decoder = QoiDecoder::new(BufReader::new(File::open(input)?))?;
             let (width, height) = decoder.dimensions();
             let color_type = decoder.color_type();
             let mut buf: Vec<u8> = vec![0; decoder.total_bytes() as usize];
             decoder.read_image(&mut buf)?;
 
-            image::save_buffer(opts.output, &buf, width, height, color_type)?;
+            image::save_buffer(output, &buf, width, height, color_type)?;
         },
         (Some(input_ext), Some(output_ext)) if input_ext == "raw" && output_ext == "qoi" => {
             let mut buf = vec![];
-            std::fs::File::open(opts.input)?.read_to_end(&mut buf)?;
+            std::fs::File::open(input)?.read_to_end(&mut buf)?;
 
-            let mut output = std::fs::File::create(opts.output)?;
+            let mut output = std::fs::File::create(output)?;
             let mut encoder = QoiEncoder::new(&mut output);
             // FIXME: Add proper error handling to the width and height.
             let (width, height) = (opts.width.expect("Width must be supplied with raw input."), opts.height.expect("Height must be supplied with raw input."));
             encoder.encode(&buf, width, height, 4, ColorSpace::Srgb)?;
         },
         (_, Some(ext)) if ext == "qoi" => {
-            let dynamic_image = image::open(opts.input)?;
+            let dynamic_image = image::open(input)?;
             let (width, height) = dynamic_image.dimensions();
             let color_type = dynamic_image.color();
 
-            let mut output = std::fs::File::create(opts.output)?;
+            let mut output = std::fs::File::create(output)?;
             let mut encoder = QoiEncoder::new(&mut output);
 
             match color_type {
@@ -90,8 +158,8 @@ fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
             }
         },
         (_, Some(ext)) if ext == "raw" => {
-            let buffer = image::open(opts.input)?.to_rgba8();
-            std::fs::File::create(opts.output)?.write_all(&buffer)?;
+            let buffer = image::open(input)?.to_rgba8();
+            std::fs::File::create(output)?.write_all(&buffer)?;
         },
         // FIXME: Figure out a way to properly word this error.
         _ => return Err("One of INPUT or OUTPUT must be a .qoi file.".into())
@@ -0,0 +1,292 @@
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    ColorSpace, DecodeMode, DecoderError, EncodeStats, EncoderError, FormatVersion, PixelFormat, QoiChunk,
+    QoiDecoderBuilder, QoiEncoderBuilder, QoiHeader, consts::QoiConsts, decoder::DecodeState, detect::Detector,
+    encoder::PixelEncoder, srgb::ColorConversion
+};
+
+// Bytes are read and encoded bytes are staged in blocks of roughly this size.
+const BUFFER_LENGTH: usize = 4096;
+
+/// Reads from an async reader in blocks so chunks can be parsed by the blocking decode logic.
+struct BlockReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize
+}
+
+impl<R: AsyncRead + Unpin> BlockReader<R> {
+    fn new(inner: R) -> Self {
+        BlockReader { inner, buf: Vec::with_capacity(BUFFER_LENGTH), pos: 0 }
+    }
+
+    /// Reads until at least `min` bytes are buffered or the stream ends, and returns everything buffered.
+    ///
+    /// Never waits for more than `min` bytes, so a stream that stays open only stalls when the bytes are needed.
+    async fn fill(&mut self, min: usize) -> io::Result<&[u8]> {
+        if self.buf.len() - self.pos < min {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+
+            while self.buf.len() < min {
+                self.buf.reserve(BUFFER_LENGTH);
+                if self.inner.read_buf(&mut self.buf).await? == 0 {
+                    break;
+                }
+            }
+        }
+
+        Ok(self.buffer())
+    }
+
+    fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Runs `f` with the buffered bytes as a blocking reader, consuming whatever it reads.
+    fn with_reader<T>(&mut self, f: impl FnOnce(&mut &[u8]) -> T) -> T {
+        let mut available = &self.buf[self.pos..];
+        let result = f(&mut available);
+        self.pos = self.buf.len() - available.len();
+        result
+    }
+}
+
+pub struct AsyncQoiDecoder<R> {
+    reader: BlockReader<R>,
+    output: PixelFormat,
+    color_space: ColorSpace,
+
+    state: DecodeState
+}
+
+impl QoiDecoderBuilder {
+    /// Builds an [`AsyncQoiDecoder`]. It only streams, so [`QoiDecoderBuilder::flip_vertical`] is refused.
+    pub async fn build_async<R: AsyncRead + Unpin>(self, reader: R) -> Result<AsyncQoiDecoder<R>, DecoderError> {
+        if self.flip_vertical {
+            return Err(DecoderError::FlipUnsupported);
+        }
+
+        let mut reader = BlockReader::new(reader);
+        reader.fill(QoiHeader::SIZE).await?;
+        let header = reader.with_reader(|reader| QoiHeader::read_from(reader))?;
+        let output = self.output.unwrap_or_else(|| PixelFormat::native(header.channels));
+        let chunk_count = self.limits.check(&header, output.bytes_per_pixel())?;
+
+        let version = match self.version {
+            Some(version) => version,
+            None => {
+                let mut detector = Detector::new(header.pixel_count(), header.color_space);
+                let mut probed = 0;
+                let version = loop {
+                    if let Some(version) = detector.version() {
+                        break version;
+                    }
+
+                    // A stream that stays open may never send the bytes that would settle a complete draft image.
+                    if probed == QoiConsts::PROBE_LENGTH
+                        || (detector.draft_complete() && reader.buffer().len() == probed) {
+                        break detector.guess();
+                    }
+
+                    // Only waits for more once everything already buffered has been probed.
+                    let available = reader.fill(probed + 1).await?;
+                    if available.len() == probed {
+                        break detector.guess();
+                    }
+
+                    for &byte in &available[probed..core::cmp::min(available.len(), QoiConsts::PROBE_LENGTH)] {
+                        detector.push(byte);
+                        probed += 1;
+                        if detector.version().is_some() {
+                            break;
+                        }
+                    }
+                };
+                log::debug!("Detected {:?} after probing {} bytes", version, probed);
+                version
+            }
+        };
+
+        self.mode.check_color_space(&header, version)?;
+        let (color_space, conversion) =
+            ColorConversion::resolve(ColorSpace::from_byte(header.color_space, version), self.color_space);
+
+        Ok(AsyncQoiDecoder {
+            reader,
+            output,
+            color_space,
+
+            state: DecodeState::new(header, self.mode, conversion, chunk_count, version, QoiHeader::SIZE as u64)
+        })
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncQoiDecoder<R> {
+    pub async fn new(reader: R) -> Result<Self, DecoderError> {
+        QoiDecoderBuilder::new().build_async(reader).await
+    }
+
+    pub async fn new_with_version(reader: R, version: FormatVersion) -> Result<Self, DecoderError> {
+        QoiDecoderBuilder::new().version(version).build_async(reader).await
+    }
+
+    pub fn format_version(&self) -> FormatVersion {
+        self.state.pixels.version
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.state.header
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.state.header.width, self.state.header.height)
+    }
+
+    pub fn channels(&self) -> u8 {
+        self.state.header.channels
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    pub fn mode(&self) -> DecodeMode {
+        self.state.mode
+    }
+
+    pub fn output_format(&self) -> PixelFormat {
+        self.output
+    }
+
+    pub async fn decode(&mut self, buf: &mut [u8]) -> Result<usize, DecoderError> {
+        let bytes_per_pixel = self.output.bytes_per_pixel() as usize;
+
+        let mut read = 0;
+        for out in buf.chunks_exact_mut(bytes_per_pixel).take(self.state.remaining()) {
+            if self.state.needs_chunk() {
+                if let Some(&first_byte) = self.fill(1).await?.first() {
+                    self.fill(QoiChunk::len_from_first_byte(first_byte, self.state.pixels.version)).await?;
+                }
+            }
+            let state = &mut self.state;
+            self.reader.with_reader(|reader| state.advance(reader))?;
+
+            self.output.write(self.state.output_pixel(), out);
+            read += bytes_per_pixel;
+        }
+
+        if self.state.chunks_read == self.state.chunk_count {
+            // Strict mode needs one byte past the trailer to see trailing data.
+            let strict = self.state.mode == DecodeMode::Strict;
+            self.fill(self.state.pixels.version.trailer().len() + strict as usize).await?;
+            let state = &mut self.state;
+            self.reader.with_reader(|reader| state.finish_pixels(reader))?;
+        }
+
+        Ok(read)
+    }
+
+    async fn fill(&mut self, min: usize) -> Result<&[u8], DecoderError> {
+        let position = self.state.position();
+        self.reader.fill(min).await.map_err(|e| DecoderError::from(e).positioned(position))
+    }
+}
+
+pub struct AsyncQoiEncoder<W> {
+    writer: W,
+    options: QoiEncoderBuilder
+}
+
+impl QoiEncoderBuilder {
+    /// Builds an [`AsyncQoiEncoder`], which takes the same options as the blocking encoder.
+    pub fn build_async<W: AsyncWrite + Unpin>(self, writer: W) -> AsyncQoiEncoder<W> {
+        AsyncQoiEncoder { writer, options: self }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncQoiEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        QoiEncoderBuilder::new().build_async(writer)
+    }
+
+    pub fn new_with_version(writer: W, version: FormatVersion) -> Self {
+        QoiEncoderBuilder::new().version(version).build_async(writer)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Encodes `buf` with the channel count and color space the encoder was built with.
+    pub async fn encode_image(&mut self, buf: &[u8], width: u32, height: u32) -> Result<EncodeStats, EncoderError> {
+        let channels = self.options.channels.unwrap_or(4);
+        let color_space = self.options.color_space.unwrap_or(ColorSpace::Srgb);
+        self.encode(buf, width, height, channels, color_space).await
+    }
+
+    pub async fn encode(
+        &mut self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<EncodeStats, EncoderError> {
+        self.options.check_options(channels, color_space)?;
+        let pixels = self.options.pixels(buf, width, height, channels)?;
+        self.encode_pixels(pixels, width as u64 * height as u64, width, height, channels, color_space).await
+    }
+
+    pub async fn encode_packed(
+        &mut self,
+        buf: &[u32],
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<EncodeStats, EncoderError> {
+        self.options.check_options(channels, color_space)?;
+        let pixels = self.options.packed_pixels(buf, width, height, channels)?;
+        self.encode_pixels(pixels, width as u64 * height as u64, width, height, channels, color_space).await
+    }
+
+    async fn encode_pixels<I: Iterator<Item = [u8; 4]>>(
+        &mut self,
+        pixels: I,
+        count: u64,
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<EncodeStats, EncoderError> {
+        let (header, pixels) = self.options.prepare(pixels, width, height, channels, color_space)?;
+        let (version, dry_run) = (self.options.version, self.options.dry_run);
+
+        let mut staging = Vec::with_capacity(BUFFER_LENGTH + QoiConsts::END_MARKER_LENGTH);
+        staging.extend_from_slice(&header.to_bytes());
+
+        let mut pixel_encoder = PixelEncoder::new(version, count);
+        for pixel in pixels {
+            pixel_encoder.push(&mut staging, pixel)?;
+
+            if staging.len() >= BUFFER_LENGTH {
+                if !dry_run {
+                    self.writer.write_all(&staging).await?;
+                }
+                staging.clear();
+            }
+        }
+
+        if !dry_run {
+            staging.extend_from_slice(version.trailer());
+            self.writer.write_all(&staging).await?;
+            self.writer.flush().await?;
+        }
+
+        Ok(pixel_encoder.finish(channels))
+    }
+}
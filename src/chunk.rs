@@ -1,16 +1,163 @@
 
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(not(feature = "std"))]
+use crate::io;
+
+use crate::{DecoderError, FormatVersion, QoiHeader, StreamPosition, consts::QoiConsts};
+
 pub use read::ReadQoiChunk;
 pub use write::WriteQoiChunk;
 
-#[derive(Debug)]
+/// A single chunk of a QOI stream, holding its fields exactly as they are stored.
+///
+/// Differences keep their bias and run lengths their offset, so `Run8(0)` is a run of one pixel and
+/// `Diff8(2, 2, 2)` is no change at all. The first seven variants belong to the draft format, the rest to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QoiChunk {
+    /// Position in the 64 entry index.
     Index(u8),
+    /// Run of 1 to 32 pixels, less one.
     Run8(u8),
+    /// Run of 33 to 8224 pixels, less 33.
     Run16(u16),
+    /// Differences of -2 to 1 per channel, biased by 2.
     Diff8(u8, u8, u8),
+    /// Red difference of -16 to 15 biased by 16, green and blue of -8 to 7 biased by 8.
     Diff16(u8, u8, u8),
+    /// Differences of -16 to 15 per channel including alpha, biased by 16.
     Diff24(u8, u8, u8, u8),
-    Color(Option<u8>, Option<u8>, Option<u8>, Option<u8>)
+    /// New values for the channels that changed.
+    Color(Option<u8>, Option<u8>, Option<u8>, Option<u8>),
+
+    /// Differences of -2 to 1 per channel, biased by 2.
+    Diff(u8, u8, u8),
+    /// Green difference of -32 to 31 biased by 32, then red and blue relative to it from -8 to 7 biased by 8.
+    Luma(u8, u8, u8),
+    /// Run of 1 to 62 pixels, less one.
+    Run(u8),
+    Rgb(u8, u8, u8),
+    Rgba(u8, u8, u8, u8)
+}
+
+impl QoiChunk {
+    pub(crate) const RUN_16_MAX: u16 = 0x1fff;
+
+    /// Number of pixels the chunk produces, which is more than one only for runs.
+    pub fn pixel_count(&self) -> u32 {
+        match *self {
+            QoiChunk::Run8(run) | QoiChunk::Run(run) => run as u32 + 1,
+            QoiChunk::Run16(run) => run as u32 + 33,
+            _ => 1
+        }
+    }
+
+    /// Number of bytes the chunk takes up in the stream.
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            QoiChunk::Index(_) | QoiChunk::Run8(_) | QoiChunk::Diff8(..) | QoiChunk::Diff(..) | QoiChunk::Run(_) => 1,
+            QoiChunk::Run16(_) | QoiChunk::Diff16(..) | QoiChunk::Luma(..) => 2,
+            QoiChunk::Diff24(..) => 3,
+            QoiChunk::Color(r, g, b, a) => 1 + [r, g, b, a].iter().filter(|channel| channel.is_some()).count(),
+            QoiChunk::Rgb(..) => 4,
+            QoiChunk::Rgba(..) => 5
+        }
+    }
+
+    /// Whether every field fits in the bits its encoding gives it.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            QoiChunk::Index(pos) => pos < 64,
+            QoiChunk::Run8(run) => run < 32,
+            QoiChunk::Run16(run) => run <= Self::RUN_16_MAX,
+            QoiChunk::Diff8(r, g, b) | QoiChunk::Diff(r, g, b) => r < 4 && g < 4 && b < 4,
+            QoiChunk::Diff16(r, g, b) => r < 32 && g < 16 && b < 16,
+            QoiChunk::Diff24(r, g, b, a) => r < 32 && g < 32 && b < 32 && a < 32,
+            QoiChunk::Luma(g, rg, bg) => g < 64 && rg < 16 && bg < 16,
+            QoiChunk::Run(run) => run < QoiConsts::V1_RUN_MAX,
+            QoiChunk::Color(..) | QoiChunk::Rgb(..) | QoiChunk::Rgba(..) => true
+        }
+    }
+
+    #[inline]
+    pub(crate) fn len_from_first_byte(first_byte: u8, version: FormatVersion) -> usize {
+        match version {
+            FormatVersion::Draft => if first_byte & QoiConsts::MASK_4 == QoiConsts::COLOR {
+                1 + (first_byte & !QoiConsts::MASK_4).count_ones() as usize
+            } else if first_byte & QoiConsts::MASK_4 == QoiConsts::DIFF_24 {
+                3
+            } else if first_byte & QoiConsts::MASK_3 == QoiConsts::DIFF_16 || first_byte & QoiConsts::MASK_3 == QoiConsts::RUN_16 {
+                2
+            } else {
+                1
+            },
+            FormatVersion::V1 => if first_byte == QoiConsts::V1_RGBA {
+                5
+            } else if first_byte == QoiConsts::V1_RGB {
+                4
+            } else if first_byte & QoiConsts::MASK_2 == QoiConsts::V1_LUMA {
+                2
+            } else {
+                1
+            }
+        }
+    }
+}
+
+/// Iterates over the chunks of a stream whose header has already been read, yielding each one with its
+/// byte offset from the start of the file and the index of the first pixel it produces.
+///
+/// Iteration stops once every pixel in the header is accounted for, leaving the padding unread.
+pub struct QoiChunks<R> {
+    reader: R,
+    version: FormatVersion,
+    offset: u64,
+    pixel: u64,
+    pixel_count: u64,
+    width: u32,
+    failed: bool
+}
+
+impl<R: io::Read> QoiChunks<R> {
+    pub fn new(reader: R, header: &QoiHeader, version: FormatVersion) -> Self {
+        QoiChunks {
+            reader,
+            version,
+            offset: QoiHeader::SIZE as u64,
+            pixel: 0,
+            pixel_count: header.pixel_count(),
+            width: header.width,
+            failed: false
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> Iterator for QoiChunks<R> {
+    type Item = Result<(u64, u64, QoiChunk), DecoderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pixel >= self.pixel_count {
+            return None;
+        }
+
+        let chunk = match self.reader.read_qoi_chunk(self.version) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                self.failed = true;
+                let position = StreamPosition::new(self.offset, self.pixel, self.width);
+                return Some(Err(e.at(position, self.pixel_count)));
+            }
+        };
+
+        let item = (self.offset, self.pixel, chunk);
+        self.offset += chunk.encoded_len() as u64;
+        self.pixel += chunk.pixel_count() as u64;
+        Some(Ok(item))
+    }
 }
 
 mod read {
@@ -19,57 +166,94 @@ mod read {
     #[cfg(not(feature = "std"))]
     use crate::io;
 
-    use crate::{DecoderError, consts::QoiConsts};
+    use crate::{DecoderError, FormatVersion, StreamPosition, consts::QoiConsts};
     use super::QoiChunk;
 
+    /// Reads chunks one at a time from anything implementing `Read`.
     pub trait ReadQoiChunk {
-        fn read_qoi_chunk(&mut self) -> Result<QoiChunk, DecoderError>;
+        fn read_qoi_chunk(&mut self, version: FormatVersion) -> Result<QoiChunk, DecoderError>;
     }
     impl<R: io::Read> ReadQoiChunk for R {
         #[inline]
-        fn read_qoi_chunk(&mut self) -> Result<QoiChunk, DecoderError> {
-            #[cfg(feature = "std")]
-            use byteorder::ReadBytesExt;
-            use QoiChunk::*;
-
-            let first_byte = self.read_u8()?;
-
-            let chunk = if first_byte & QoiConsts::MASK_2 == QoiConsts::INDEX {
-                Index(first_byte ^ QoiConsts::INDEX)
-            } else if first_byte & QoiConsts::MASK_3 == QoiConsts::RUN_8 {
-                Run8(first_byte ^ QoiConsts::RUN_8)
-            } else if first_byte & QoiConsts::MASK_3 == QoiConsts::RUN_16 {
-                let (first_byte, second_byte) = (first_byte as u16, self.read_u8()? as u16);
-                Run16((((first_byte ^ QoiConsts::RUN_16 as u16) << 8) | second_byte) + 32)
-            } else if first_byte & QoiConsts::MASK_2 == QoiConsts::DIFF_8 {
-                Diff8((first_byte >> 4) & 0x03, (first_byte >> 2) & 0x03, first_byte & 0x03)
-            } else if first_byte & QoiConsts::MASK_3 == QoiConsts::DIFF_16 {
-                let second_byte = self.read_u8()?;
-                Diff16(first_byte & 0x1f, second_byte >> 4, second_byte & 0x0f)
-            } else if first_byte & QoiConsts::MASK_4 == QoiConsts::DIFF_24 {
-                let second_byte = self.read_u8()?;
-                let third_byte = self.read_u8()?;
-                Diff24(
-                    ((first_byte & 0x0f) << 1) | (second_byte >> 7),
-                    (second_byte & 0x7c) >> 2,
-                    ((second_byte & 0x03) << 3) | ((third_byte & 0xe0) >> 5),
-                    third_byte & 0x1f
-                )
-            } else if first_byte & QoiConsts::MASK_4 == QoiConsts::COLOR {
-                Color(
-                    if first_byte & QoiConsts::COLOR_R != 0 { Some(self.read_u8()?) } else { None },
-                    if first_byte & QoiConsts::COLOR_G != 0 { Some(self.read_u8()?) } else { None },
-                    if first_byte & QoiConsts::COLOR_B != 0 { Some(self.read_u8()?) } else { None },
-                    if first_byte & QoiConsts::COLOR_A != 0 { Some(self.read_u8()?) } else { None },
-                )
-            } else {
-                return Err(DecoderError::InvalidChunkStart(first_byte));
+        fn read_qoi_chunk(&mut self, version: FormatVersion) -> Result<QoiChunk, DecoderError> {
+            let chunk = match version {
+                FormatVersion::Draft => read_draft_chunk(self)?,
+                FormatVersion::V1 => read_v1_chunk(self)?
             };
 
             log::trace!("{:?}", chunk);
             Ok(chunk)
         }
     }
+
+    #[inline]
+    fn read_draft_chunk<R: io::Read>(reader: &mut R) -> Result<QoiChunk, DecoderError> {
+        #[cfg(feature = "std")]
+        use byteorder::ReadBytesExt;
+        use QoiChunk::*;
+
+        let first_byte = reader.read_u8()?;
+
+        let chunk = if first_byte & QoiConsts::MASK_2 == QoiConsts::INDEX {
+            Index(first_byte ^ QoiConsts::INDEX)
+        } else if first_byte & QoiConsts::MASK_3 == QoiConsts::RUN_8 {
+            Run8(first_byte ^ QoiConsts::RUN_8)
+        } else if first_byte & QoiConsts::MASK_3 == QoiConsts::RUN_16 {
+            let (first_byte, second_byte) = (first_byte as u16, reader.read_u8()? as u16);
+            Run16(((first_byte ^ QoiConsts::RUN_16 as u16) << 8) | second_byte)
+        } else if first_byte & QoiConsts::MASK_2 == QoiConsts::DIFF_8 {
+            Diff8((first_byte >> 4) & 0x03, (first_byte >> 2) & 0x03, first_byte & 0x03)
+        } else if first_byte & QoiConsts::MASK_3 == QoiConsts::DIFF_16 {
+            let second_byte = reader.read_u8()?;
+            Diff16(first_byte & 0x1f, second_byte >> 4, second_byte & 0x0f)
+        } else if first_byte & QoiConsts::MASK_4 == QoiConsts::DIFF_24 {
+            let second_byte = reader.read_u8()?;
+            let third_byte = reader.read_u8()?;
+            Diff24(
+                ((first_byte & 0x0f) << 1) | (second_byte >> 7),
+                (second_byte & 0x7c) >> 2,
+                ((second_byte & 0x03) << 3) | ((third_byte & 0xe0) >> 5),
+                third_byte & 0x1f
+            )
+        } else if first_byte & QoiConsts::MASK_4 == QoiConsts::COLOR {
+            Color(
+                if first_byte & QoiConsts::COLOR_R != 0 { Some(reader.read_u8()?) } else { None },
+                if first_byte & QoiConsts::COLOR_G != 0 { Some(reader.read_u8()?) } else { None },
+                if first_byte & QoiConsts::COLOR_B != 0 { Some(reader.read_u8()?) } else { None },
+                if first_byte & QoiConsts::COLOR_A != 0 { Some(reader.read_u8()?) } else { None },
+            )
+        } else {
+            return Err(DecoderError::InvalidChunkStart { byte: first_byte, position: StreamPosition::default() });
+        };
+
+        Ok(chunk)
+    }
+
+    #[inline]
+    fn read_v1_chunk<R: io::Read>(reader: &mut R) -> Result<QoiChunk, DecoderError> {
+        #[cfg(feature = "std")]
+        use byteorder::ReadBytesExt;
+        use QoiChunk::*;
+
+        let first_byte = reader.read_u8()?;
+
+        let chunk = if first_byte == QoiConsts::V1_RGB {
+            Rgb(reader.read_u8()?, reader.read_u8()?, reader.read_u8()?)
+        } else if first_byte == QoiConsts::V1_RGBA {
+            Rgba(reader.read_u8()?, reader.read_u8()?, reader.read_u8()?, reader.read_u8()?)
+        } else if first_byte & QoiConsts::MASK_2 == QoiConsts::V1_INDEX {
+            Index(first_byte ^ QoiConsts::V1_INDEX)
+        } else if first_byte & QoiConsts::MASK_2 == QoiConsts::V1_DIFF {
+            Diff((first_byte >> 4) & 0x03, (first_byte >> 2) & 0x03, first_byte & 0x03)
+        } else if first_byte & QoiConsts::MASK_2 == QoiConsts::V1_LUMA {
+            let second_byte = reader.read_u8()?;
+            Luma(first_byte & 0x3f, second_byte >> 4, second_byte & 0x0f)
+        } else {
+            Run(first_byte ^ QoiConsts::V1_RUN)
+        };
+
+        Ok(chunk)
+    }
 }
 
 mod write {
@@ -81,7 +265,10 @@ mod write {
     use crate::{consts::QoiConsts, error::EncoderError};
     use super::QoiChunk;
 
+    /// Writes chunks one at a time to anything implementing `Write`.
     pub trait WriteQoiChunk {
+        /// Writes `chunk`, returning how many bytes it took. Fields that don't fit their encoding are
+        /// rejected with [`EncoderError::InvalidChunk`] before anything is written.
         fn write_qoi_chunk(&mut self, chunk: QoiChunk) -> Result<usize, EncoderError>;
     }
 
@@ -92,7 +279,11 @@ mod write {
             use byteorder::WriteBytesExt;
             use QoiChunk::*;
 
-            let mut wrote = 1;        
+            if !chunk.is_valid() {
+                return Err(EncoderError::InvalidChunk(chunk));
+            }
+
+            let mut wrote = 1;
             match chunk {
                 Index(pos) => self.write_u8(QoiConsts::INDEX | pos)?,
                 Run8(run) => self.write_u8(QoiConsts::RUN_8 | run)?,
@@ -125,6 +316,21 @@ mod write {
                     if let Some(b) = b { self.write_u8(b)?; wrote += 1; }
                     if let Some(a) = a { self.write_u8(a)?; wrote += 1; }
                 },
+                Diff(r, g, b) => self.write_u8(QoiConsts::V1_DIFF | (r << 4) | (g << 2) | b)?,
+                Luma(g, rg, bg) => {
+                    self.write_u8(QoiConsts::V1_LUMA | g)?;
+                    self.write_u8((rg << 4) | bg)?;
+                    wrote += 1;
+                },
+                Run(run) => self.write_u8(QoiConsts::V1_RUN | run)?,
+                Rgb(r, g, b) => {
+                    self.write_all(&[QoiConsts::V1_RGB, r, g, b])?;
+                    wrote += 3;
+                },
+                Rgba(r, g, b, a) => {
+                    self.write_all(&[QoiConsts::V1_RGBA, r, g, b, a])?;
+                    wrote += 4;
+                },
             }
 
             log::trace!("{:?}", chunk);
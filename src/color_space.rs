@@ -1,6 +1,7 @@
 
-use crate::consts::QoiConsts;
+use crate::{FormatVersion, consts::QoiConsts};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorSpace {
     Srgb,
     SrgbLinearAlpha,
@@ -45,3 +46,44 @@ impl From<ColorSpace> for u8 {
         }
     }
 }
+
+impl ColorSpace {
+    pub fn from_byte(byte: u8, version: FormatVersion) -> Self {
+        match version {
+            FormatVersion::Draft => byte.into(),
+            FormatVersion::V1 => match byte {
+                QoiConsts::V1_SRGB => ColorSpace::SrgbLinearAlpha,
+                QoiConsts::V1_LINEAR => ColorSpace::Linear,
+                byte => ColorSpace::Unknown(byte)
+            }
+        }
+    }
+
+    /// Which of the red, green, blue and alpha channels are stored linearly rather than sRGB encoded.
+    ///
+    /// Returns `None` for unknown color spaces.
+    pub fn linear_channels(self) -> Option<[bool; 4]> {
+        match self {
+            ColorSpace::Srgb => Some([false; 4]),
+            ColorSpace::SrgbLinearAlpha => Some([false, false, false, true]),
+            ColorSpace::Linear => Some([true; 4]),
+            ColorSpace::Custom(r, g, b, a) => Some([r, g, b, a]),
+            ColorSpace::Unknown(_) => None
+        }
+    }
+
+    /// Returns `None` when the color space can't be represented by `version`.
+    pub fn to_byte(self, version: FormatVersion) -> Option<u8> {
+        match version {
+            FormatVersion::Draft => Some(self.into()),
+            FormatVersion::V1 => match self {
+                ColorSpace::Srgb | ColorSpace::SrgbLinearAlpha
+                    | ColorSpace::Custom(false, false, false, _) => Some(QoiConsts::V1_SRGB),
+                ColorSpace::Linear
+                    | ColorSpace::Custom(true, true, true, _) => Some(QoiConsts::V1_LINEAR),
+                ColorSpace::Unknown(byte) => Some(byte),
+                ColorSpace::Custom(..) => None
+            }
+        }
+    }
+}
@@ -17,6 +17,13 @@ impl QoiConsts {
     pub const COLOR_B: u8 = 0b00000010;
     pub const COLOR_A: u8 = 0b00000001;
 
+    pub const V1_INDEX: u8 = 0b00000000;
+    pub const V1_DIFF: u8 = 0b01000000;
+    pub const V1_LUMA: u8 = 0b10000000;
+    pub const V1_RUN: u8 = 0b11000000;
+    pub const V1_RGB: u8 = 0b11111110;
+    pub const V1_RGBA: u8 = 0b11111111;
+
     pub const MASK_2: u8 = 0b11000000;
     pub const MASK_3: u8 = 0b11100000;
     pub const MASK_4: u8 = 0b11110000;
@@ -29,11 +36,23 @@ impl QoiConsts {
     pub const LINEAR_B: u8 = 0b00000010;
     pub const LINEAR_A: u8 = 0b00000001;
 
+    pub const V1_SRGB: u8 = 0x00;
+    pub const V1_LINEAR: u8 = 0x01;
+
     pub const MAGIC_LEN: usize = 4;
     pub const INDEX_SIZE: usize = 64;
     pub const PADDING_LENGTH: usize = 4;
     pub const PADDING:[u8; Self::PADDING_LENGTH] = [0; Self::PADDING_LENGTH];
 
+    pub const END_MARKER_LENGTH: usize = 8;
+    pub const END_MARKER: [u8; Self::END_MARKER_LENGTH] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+    pub const V1_RUN_MAX: u8 = 62;
+
+    pub const CHUNK_LENGTH_MAX: usize = 5;
+
+    pub const PROBE_LENGTH: usize = 4096;
+
     pub const CHANNELS_MIN: u8 = 3;
     pub const CHANNELS_MAX: u8 = 4;
 
@@ -41,4 +60,9 @@ impl QoiConsts {
     pub fn pixel_hash(pixel: &[u8]) -> usize {
         pixel.iter().fold(0, |a, c| a ^ c) as usize % Self::INDEX_SIZE
     }
+
+    #[inline(always)]
+    pub fn pixel_hash_v1(pixel: &[u8]) -> usize {
+        (pixel[0] as usize * 3 + pixel[1] as usize * 5 + pixel[2] as usize * 7 + pixel[3] as usize * 11) % Self::INDEX_SIZE
+    }
 }
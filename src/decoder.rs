@@ -1,128 +1,869 @@
 #[cfg(feature = "std")]
-use std::io;
+use std::io::{self, Read};
 #[cfg(not(feature = "std"))]
-use crate::io;
+use crate::io::{self, Read};
 
-use byteorder::BigEndian;
-#[cfg(feature = "std")]
-use byteorder::ReadBytesExt;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    ColorSpace, DecoderError, DecoderLimits, FormatVersion, PixelFormat, QoiChunk, QoiHeader, ReadQoiChunk,
+    StreamPosition, consts::*, detect::{Detector, Replay}, srgb::ColorConversion
+};
+
+pub(crate) struct PixelState {
+    pub(crate) version: FormatVersion,
+    pub(crate) run: usize,
+    pub(crate) pixel: [u8; 4],
+    pub(crate) index: [[u8; 4]; QoiConsts::INDEX_SIZE]
+}
+
+impl PixelState {
+    pub(crate) fn new(version: FormatVersion) -> Self {
+        PixelState {
+            version,
+            run: 0,
+            pixel: [0, 0, 0, 255],
+            index: [[0; 4]; QoiConsts::INDEX_SIZE]
+        }
+    }
+
+    #[inline]
+    pub(crate) fn index_position(&self) -> usize {
+        match self.version {
+            FormatVersion::Draft => QoiConsts::pixel_hash(&self.pixel),
+            FormatVersion::V1 => QoiConsts::pixel_hash_v1(&self.pixel)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn apply(&mut self, chunk: QoiChunk) {
+        match chunk {
+            QoiChunk::Index(pos) => self.pixel.copy_from_slice(&self.index[pos as usize]),
+            QoiChunk::Run8(run) => {
+                self.run = run as usize;
+                return;
+            },
+            QoiChunk::Run16(run) => {
+                self.run = run as usize + 32;
+                return;
+            },
+            QoiChunk::Diff8(r, g, b) => {
+                self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(2);
+                self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(2);
+                self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(2);
+            },
+            QoiChunk::Diff16(r, g, b) => {
+                self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(16);
+                self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(8);
+                self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(8);
+            },
+            QoiChunk::Diff24(r, g, b, a) => {
+                self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(16);
+                self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(16);
+                self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(16);
+                self.pixel[3] = self.pixel[3].wrapping_add(a).wrapping_sub(16);
+            },
+            QoiChunk::Color(r, g, b, a) => {
+                if let Some(r) = r { self.pixel[0] = r; }
+                if let Some(g) = g { self.pixel[1] = g; }
+                if let Some(b) = b { self.pixel[2] = b; }
+                if let Some(a) = a { self.pixel[3] = a; }
+            },
+            QoiChunk::Diff(r, g, b) => {
+                self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(2);
+                self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(2);
+                self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(2);
+            },
+            QoiChunk::Luma(g, rg, bg) => {
+                let g = g.wrapping_sub(32);
+                self.pixel[0] = self.pixel[0].wrapping_add(g).wrapping_add(rg).wrapping_sub(8);
+                self.pixel[1] = self.pixel[1].wrapping_add(g);
+                self.pixel[2] = self.pixel[2].wrapping_add(g).wrapping_add(bg).wrapping_sub(8);
+            },
+            QoiChunk::Run(run) => {
+                self.run = run as usize;
+                return;
+            },
+            QoiChunk::Rgb(r, g, b) => {
+                self.pixel[0] = r;
+                self.pixel[1] = g;
+                self.pixel[2] = b;
+            },
+            QoiChunk::Rgba(r, g, b, a) => self.pixel = [r, g, b, a],
+        }
+
+        let index_pos = self.index_position();
+        self.index[index_pos].copy_from_slice(&self.pixel);
+    }
+}
+
+/// Readers that can tell whether anything follows the trailer without losing it.
+pub(crate) trait PeekEnd: Read {
+    fn at_end(&mut self) -> io::Result<bool>;
+}
+
+impl PeekEnd for &[u8] {
+    fn at_end(&mut self) -> io::Result<bool> {
+        Ok(self.is_empty())
+    }
+}
+
+/// Progress through the chunks and trailer of one image, shared by the blocking and async decoders.
+pub(crate) struct DecodeState {
+    pub(crate) header: QoiHeader,
+    pub(crate) mode: DecodeMode,
+    pub(crate) conversion: ColorConversion,
+
+    pub(crate) chunk_count: usize,
+    pub(crate) chunks_read: usize,
+    pub(crate) truncated: bool,
+    pub(crate) offset: u64,
+
+    pub(crate) pixels: PixelState
+}
+
+impl DecodeState {
+    pub(crate) fn new(
+        header: QoiHeader,
+        mode: DecodeMode,
+        conversion: ColorConversion,
+        chunk_count: usize,
+        version: FormatVersion,
+        offset: u64
+    ) -> Self {
+        DecodeState {
+            header,
+            mode,
+            conversion,
+
+            chunk_count,
+            chunks_read: 0,
+            truncated: false,
+            offset,
+
+            pixels: PixelState::new(version)
+        }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.chunk_count.saturating_sub(self.chunks_read)
+    }
+
+    /// Whether the next call to [`DecodeState::advance`] reads a chunk.
+    #[cfg(feature = "async")]
+    pub(crate) fn needs_chunk(&self) -> bool {
+        self.pixels.run == 0 && !self.truncated
+    }
+
+    pub(crate) fn advance<R: Read>(&mut self, reader: &mut R) -> Result<(), DecoderError> {
+        if self.pixels.run > 0 {
+            self.pixels.run -= 1;
+        } else if !self.truncated {
+            match reader.read_qoi_chunk(self.pixels.version) {
+                Ok(qoi_chunk) => {
+                    self.offset += qoi_chunk.encoded_len() as u64;
+                    self.pixels.apply(qoi_chunk)
+                },
+                Err(e) if self.mode == DecodeMode::Lenient && e.is_eof() => {
+                    log::warn!("QOI stream ended after {} of {} pixels", self.chunks_read, self.chunk_count);
+                    self.truncated = true;
+                },
+                Err(e) => return Err(e.at(self.position(), self.chunk_count as u64))
+            }
+        }
+
+        self.chunks_read += 1;
+        Ok(())
+    }
+
+    /// Checks the end of the stream once the last pixel has been decoded.
+    pub(crate) fn finish_pixels<R: PeekEnd>(&mut self, reader: &mut R) -> Result<(), DecoderError> {
+        if self.chunks_read == self.chunk_count {
+            self.chunks_read += 1;
+            if self.mode == DecodeMode::Strict && self.pixels.run > 0 {
+                return Err(DecoderError::RunOverflow { overflow: self.pixels.run, position: self.position() });
+            }
+            self.read_trailer(reader).map_err(|e| e.at(self.position(), self.chunk_count as u64))?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn position(&self) -> StreamPosition {
+        let pixel = core::cmp::min(self.chunks_read, self.chunk_count) as u64;
+        StreamPosition::new(self.offset, pixel, self.header.width)
+    }
+
+    pub(crate) fn output_pixel(&self) -> [u8; 4] {
+        let mut pixel = self.pixels.pixel;
+        if self.header.channels == 3 {
+            pixel[3] = 255;
+        }
+        self.conversion.apply(pixel)
+    }
+
+    fn read_trailer<R: PeekEnd>(&mut self, reader: &mut R) -> Result<(), DecoderError> {
+        if self.truncated {
+            return Ok(());
+        }
 
-use crate::{ColorSpace, DecoderError, QoiChunk, ReadQoiChunk, consts::*};
+        let mut trailer = [0; QoiConsts::END_MARKER_LENGTH];
+        let trailer = &mut trailer[..self.pixels.version.trailer().len()];
+        match reader.read_exact(trailer) {
+            Err(e) if self.mode == DecodeMode::Lenient => {
+                log::warn!("QOI stream is missing its padding: {}", e);
+                return Ok(());
+            },
+            result => result?
+        }
+        self.mode.check_trailer(self.pixels.version, trailer)?;
+        self.offset += trailer.len() as u64;
+
+        if self.mode == DecodeMode::Strict && !reader.at_end()? {
+            return Err(DecoderError::TrailingData { position: StreamPosition::default() });
+        }
+
+        Ok(())
+    }
+}
 
 pub struct QoiDecoder<R> {
-    reader: R,
+    reader: Replay<R>,
+    options: QoiDecoderBuilder,
+    output: PixelFormat,
+    color_space: ColorSpace,
 
-    width: u32,
-    height: u32,
-    channels: u8,
-    color_space: u8,
+    // Pixels decoded ahead for `BufRead` and reads that end partway through a pixel.
+    read_buf: Vec<u8>,
+    read_pos: usize,
 
-    chunk_count: usize,
-    chunks_read: usize,
+    state: DecodeState
+}
 
-    run: usize,
-    pixel: [u8; 4],
-    index: [[u8; 4]; QoiConsts::INDEX_SIZE]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    /// Rejects unknown color spaces, trailing data and runs past the end of the image.
+    Strict,
+    /// Requires valid padding but otherwise decodes whatever the stream describes.
+    #[default]
+    Normal,
+    /// Accepts missing or garbled padding and fills pixels lost to a truncated stream with the last pixel.
+    Lenient
 }
 
-impl<R: io::Read> QoiDecoder<R> {
-    pub fn new(mut reader: R) -> Result<Self, DecoderError> {
-        let mut signature = [0; QoiConsts::MAGIC_LEN];
-        reader.read_exact(&mut signature)?;
-        if signature != QoiConsts::MAGIC {
-            return Err(DecoderError::InvalidSignature(signature));
+impl DecodeMode {
+    pub(crate) fn check_color_space(self, header: &QoiHeader, version: FormatVersion) -> Result<(), DecoderError> {
+        if self == DecodeMode::Strict {
+            if let ColorSpace::Unknown(color_space) = ColorSpace::from_byte(header.color_space, version) {
+                return Err(DecoderError::InvalidColorSpace { color_space, position: StreamPosition::default() });
+            }
         }
 
-        let width = reader.read_u32::<BigEndian>()?;
-        let height = reader.read_u32::<BigEndian>()?;
-        let channels = reader.read_u8()?;
-        let color_space = reader.read_u8()?;
+        Ok(())
+    }
 
-        if !(QoiConsts::CHANNELS_MIN..=QoiConsts::CHANNELS_MAX).contains(&channels) {
-            return Err(DecoderError::InvalidChannelCount(channels));
+    pub(crate) fn check_trailer(self, version: FormatVersion, trailer: &[u8]) -> Result<(), DecoderError> {
+        if trailer == version.trailer() {
+            return Ok(());
         }
-        
-        let decoder = QoiDecoder {
-            reader,
 
-            width,
-            height,
-            channels,
-            color_space,
+        if self == DecodeMode::Lenient {
+            log::warn!("QOI stream has invalid padding ({:?})", trailer);
+            return Ok(());
+        }
 
-            chunk_count: width as usize * height as usize,
-            chunks_read: 0,
+        Err(match version {
+            FormatVersion::Draft => {
+                let mut padding = [0; QoiConsts::PADDING_LENGTH];
+                padding.copy_from_slice(trailer);
+                DecoderError::InvalidPadding { padding, position: StreamPosition::default() }
+            },
+            FormatVersion::V1 => {
+                let mut end_marker = [0; QoiConsts::END_MARKER_LENGTH];
+                end_marker.copy_from_slice(trailer);
+                DecoderError::InvalidEndMarker { end_marker, position: StreamPosition::default() }
+            }
+        })
+    }
+}
 
-            run: 0,
-            pixel: [0, 0, 0, 255],
-            index: [[0; 4]; QoiConsts::INDEX_SIZE],
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QoiDecoderBuilder {
+    pub(crate) version: Option<FormatVersion>,
+    pub(crate) limits: DecoderLimits,
+    pub(crate) mode: DecodeMode,
+    pub(crate) output: Option<PixelFormat>,
+    pub(crate) flip_vertical: bool,
+    pub(crate) color_space: Option<ColorSpace>
+}
+
+impl QoiDecoderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skips detection and decodes the stream as `version`.
+    pub fn version(mut self, version: FormatVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn limits(mut self, limits: DecoderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn mode(mut self, mode: DecodeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Converts pixels to `format` while decoding instead of returning the image's own layout.
+    pub fn output_format(mut self, format: PixelFormat) -> Self {
+        self.output = Some(format);
+        self
+    }
+
+    /// Writes rows bottom-up, and reports rows bottom-up from the push decoder. [`QoiDecoder::decode`] then needs
+    /// the whole image in one buffer, and the streaming readers refuse to run.
+    pub fn flip_vertical(mut self, flip: bool) -> Self {
+        self.flip_vertical = flip;
+        self
+    }
+
+    /// Converts each channel between sRGB and linear encoding so pixels come out in `color_space`,
+    /// unless the image's own color space is unknown.
+    pub fn target_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = Some(color_space);
+        self
+    }
+
+    pub fn build<R: io::Read>(self, reader: R) -> Result<QoiDecoder<R>, DecoderError> {
+        let mut reader = Replay::new(Vec::new(), reader);
+        let header = QoiHeader::read_from(&mut reader)?;
+
+        let mut decoder = QoiDecoder {
+            reader,
+            options: self,
+            output: PixelFormat::native(header.channels),
+            color_space: ColorSpace::Srgb,
+
+            read_buf: Vec::new(),
+            read_pos: 0,
+
+            state: DecodeState::new(header, self.mode, ColorConversion::IDENTITY, 0, FormatVersion::V1, 0)
         };
+        decoder.start(header)?;
+
         Ok(decoder)
     }
+}
+
+impl QoiDecoder<()> {
+    pub fn builder() -> QoiDecoderBuilder {
+        QoiDecoderBuilder::new()
+    }
+}
+
+impl<R: io::Read> QoiDecoder<R> {
+    /// Reads the header and probes the start of the stream to tell the draft layout apart from QOI 1.0.
+    ///
+    /// Use [`QoiDecoder::new_with_version`] when the version is known, or the probe guesses wrong.
+    pub fn new(reader: R) -> Result<Self, DecoderError> {
+        QoiDecoderBuilder::new().build(reader)
+    }
+
+    pub fn new_with_version(reader: R, version: FormatVersion) -> Result<Self, DecoderError> {
+        QoiDecoderBuilder::new().version(version).build(reader)
+    }
+
+    pub fn format_version(&self) -> FormatVersion {
+        self.state.pixels.version
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.state.header
+    }
 
     pub fn dimensions(&self) -> (u32, u32) {
-        (self.width, self.height)
+        (self.state.header.width, self.state.header.height)
     }
 
     pub fn channels(&self) -> u8 {
-        self.channels
+        self.state.header.channels
     }
 
+    /// The color space of the decoded pixels, which is the target color space when one was requested.
     pub fn color_space(&self) -> ColorSpace {
-        self.color_space.into()
+        self.color_space
+    }
+
+    pub fn mode(&self) -> DecodeMode {
+        self.options.mode
+    }
+
+    pub fn output_format(&self) -> PixelFormat {
+        self.output
     }
 
+    /// Decodes as many whole pixels as fit in `buf`, continuing where the last call stopped.
+    ///
+    /// With [`QoiDecoderBuilder::flip_vertical`] set, `buf` has to hold the whole image and rows are written
+    /// bottom-up; anything smaller fails with [`DecoderError::FlipUnsupported`].
     pub fn decode(&mut self, buf: &mut [u8]) -> Result<usize, DecoderError> {
-        let mut read = 0;
-        for chunk in buf.chunks_exact_mut(self.channels as usize).take(self.chunk_count - self.chunks_read) {
-            if self.run > 0 {
-                self.run -= 1;
-            } else {
-                match self.reader.read_qoi_chunk()? {
-                    QoiChunk::Index(pos) => self.pixel.copy_from_slice(&self.index[pos as usize]),
-                    QoiChunk::Run8(run) => self.run = run as usize,
-                    QoiChunk::Run16(run) => self.run = run as usize,
-                    QoiChunk::Diff8(r, g, b) => {
-                        self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(2);
-                        self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(2);
-                        self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(2);
-                    },
-                    QoiChunk::Diff16(r, g, b) => {
-                        self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(16);
-                        self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(8);
-                        self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(8);
-                    },
-                    QoiChunk::Diff24(r, g, b, a) => {
-                        self.pixel[0] = self.pixel[0].wrapping_add(r).wrapping_sub(16);
-                        self.pixel[1] = self.pixel[1].wrapping_add(g).wrapping_sub(16);
-                        self.pixel[2] = self.pixel[2].wrapping_add(b).wrapping_sub(16);
-                        self.pixel[3] = self.pixel[3].wrapping_add(a).wrapping_sub(16);
-                    },
-                    QoiChunk::Color(r, g, b, a) => {
-                        if let Some(r) = r { self.pixel[0] = r; }
-                        if let Some(g) = g { self.pixel[1] = g; }
-                        if let Some(b) = b { self.pixel[2] = b; }
-                        if let Some(a) = a { self.pixel[3] = a; }
-                    },
+        if self.options.flip_vertical && self.state.remaining() > 0 {
+            let len = self.state.header.pixel_count() as usize * self.output.bytes_per_pixel() as usize;
+            if self.state.chunks_read > 0 || buf.len() < len {
+                return Err(DecoderError::FlipUnsupported);
+            }
+
+            let row_len = self.state.header.width as usize * self.output.bytes_per_pixel() as usize;
+            self.decode_into_strided(&mut buf[..len], row_len)?;
+            return Ok(len);
+        }
+
+        let read = self.decode_pixels(buf)?;
+        self.finish_pixels()?;
+
+        Ok(read)
+    }
+
+    /// Decodes the whole image, starting each row `stride` bytes after the previous one.
+    ///
+    /// Bytes between the end of a row and the start of the next are left untouched.
+    pub fn decode_into_strided(&mut self, buf: &mut [u8], stride: usize) -> Result<(), DecoderError> {
+        if self.state.chunks_read > 0 {
+            return Err(DecoderError::DecodeStarted { decoded_pixels: self.state.chunks_read as u64 });
+        }
+
+        let row_len = self.state.header.width as usize * self.output.bytes_per_pixel() as usize;
+        if stride < row_len {
+            return Err(DecoderError::InvalidStride { stride, row_len });
+        }
+
+        let height = self.state.header.height as usize;
+        let expected = match height {
+            0 => 0,
+            _ => stride.checked_mul(height - 1).and_then(|len| len.checked_add(row_len)).unwrap_or(usize::MAX)
+        };
+        if buf.len() < expected {
+            return Err(DecoderError::BufferTooSmall { expected, actual: buf.len() });
+        }
+
+        for y in 0..height {
+            let start = self.destination_row(y, height) * stride;
+            self.decode_pixels(&mut buf[start..start + row_len])?;
+        }
+
+        self.finish_pixels()
+    }
+
+    /// Decodes only the `width` by `height` rectangle at (`x`, `y`) into `buf`, tightly packed.
+    ///
+    /// The stream is still read in order up to the last pixel of the rectangle, but nothing after it is read.
+    pub fn decode_region(&mut self, x: u32, y: u32, width: u32, height: u32, buf: &mut [u8]) -> Result<(), DecoderError> {
+        let (image_width, image_height) = self.dimensions();
+        let first_pixel = y as usize * image_width as usize + x as usize;
+        if x > image_width || width > image_width - x
+            || y > image_height || height > image_height - y
+            || (width > 0 && height > 0 && self.state.chunks_read > first_pixel) {
+            return Err(DecoderError::InvalidRegion { x, y, width, height });
+        }
+
+        let bytes_per_pixel = self.output.bytes_per_pixel() as usize;
+        let row_len = width as usize * bytes_per_pixel;
+        let expected = row_len * height as usize;
+        if buf.len() < expected {
+            return Err(DecoderError::BufferTooSmall { expected, actual: buf.len() });
+        }
+
+        if row_len == 0 {
+            return Ok(());
+        }
+
+        for (y, row_start) in (first_pixel..).step_by(image_width as usize).take(height as usize).enumerate() {
+            while self.state.chunks_read < row_start {
+                self.advance()?;
+            }
+
+            let start = self.destination_row(y, height as usize) * row_len;
+            for out in buf[start..start + row_len].chunks_exact_mut(bytes_per_pixel) {
+                self.advance()?;
+                self.output.write(self.state.output_pixel(), out);
+            }
+        }
+
+        self.finish_pixels()
+    }
+
+    /// The size of the image scaled down by `denominator`, rounding up, as produced by
+    /// [`QoiDecoder::decode_scaled_by`]. Returns `None` for a denominator of zero.
+    pub fn scaled_dimensions(&self, denominator: u32) -> Option<(u32, u32)> {
+        let (width, height) = self.dimensions();
+        match denominator {
+            0 => None,
+            _ => Some((width.div_ceil(denominator), height.div_ceil(denominator)))
+        }
+    }
+
+    /// Decodes the whole image scaled down by `denominator`, averaging each `denominator` by `denominator` box
+    /// of pixels. Boxes on the right and bottom edges are cut short when the size isn't a multiple.
+    pub fn decode_scaled_by(&mut self, denominator: u32, buf: &mut [u8]) -> Result<(), DecoderError> {
+        let (width, height) = self.scaled_dimensions(denominator)
+            .ok_or(DecoderError::InvalidScale { width: 0, height: 0 })?;
+        self.decode_box_filtered(width, height, |coordinate, _, _| coordinate / denominator, buf)
+    }
+
+    /// Decodes the whole image box filtered down to `width` by `height`, keeping only one row of sums in memory.
+    ///
+    /// Colors are weighted by alpha so fully transparent pixels don't bleed into their neighbours.
+    pub fn decode_scaled(&mut self, width: u32, height: u32, buf: &mut [u8]) -> Result<(), DecoderError> {
+        let (image_width, image_height) = self.dimensions();
+        if width > image_width || height > image_height || (width == 0) != (image_width == 0)
+            || (height == 0) != (image_height == 0) {
+            return Err(DecoderError::InvalidScale { width, height });
+        }
+
+        let bin = |coordinate, image_len, len| (coordinate as u64 * len as u64 / image_len as u64) as u32;
+        self.decode_box_filtered(width, height, bin, buf)
+    }
+
+    /// Sums pixels into the output pixel `bin(coordinate, image_len, len)` picks along each axis.
+    fn decode_box_filtered(
+        &mut self,
+        width: u32,
+        height: u32,
+        bin: impl Fn(u32, u32, u32) -> u32,
+        buf: &mut [u8]
+    ) -> Result<(), DecoderError> {
+        let (image_width, image_height) = self.dimensions();
+        if self.state.chunks_read > 0 {
+            return Err(DecoderError::DecodeStarted { decoded_pixels: self.state.chunks_read as u64 });
+        }
+
+        let bytes_per_pixel = self.output.bytes_per_pixel() as usize;
+        let row_len = width as usize * bytes_per_pixel;
+        let expected = row_len * height as usize;
+        if buf.len() < expected {
+            return Err(DecoderError::BufferTooSmall { expected, actual: buf.len() });
+        }
+
+        if expected == 0 {
+            return self.finish_pixels();
+        }
+
+        // Alpha weighted color sums, alpha sum and pixel count for each output column.
+        let mut sums = vec![[0u64; 5]; width as usize];
+        for y in 0..image_height {
+            for x in 0..image_width {
+                self.advance()?;
+                let pixel = self.state.output_pixel();
+                let alpha = pixel[3] as u64;
+
+                let sum = &mut sums[bin(x, image_width, width) as usize];
+                for channel in 0..3 {
+                    sum[channel] += pixel[channel] as u64 * alpha;
                 }
+                sum[3] += alpha;
+                sum[4] += 1;
+            }
 
-                self.index[QoiConsts::pixel_hash(&self.pixel)].copy_from_slice(&self.pixel);
+            let out_y = bin(y, image_height, height);
+            if y + 1 < image_height && bin(y + 1, image_height, height) == out_y {
+                continue;
             }
 
-            chunk[..self.channels as usize].copy_from_slice(&self.pixel[..self.channels as usize]);
-            read += self.channels as usize;
-            self.chunks_read += 1;
+            let start = self.destination_row(out_y as usize, height as usize) * row_len;
+            for (sum, out) in sums.iter_mut().zip(buf[start..start + row_len].chunks_exact_mut(bytes_per_pixel)) {
+                let [r, g, b, alpha, count] = core::mem::take(sum);
+                let pixel = match alpha {
+                    0 => [0, 0, 0, 0],
+                    _ => [
+                        ((r + alpha / 2) / alpha) as u8,
+                        ((g + alpha / 2) / alpha) as u8,
+                        ((b + alpha / 2) / alpha) as u8,
+                        ((alpha + count / 2) / count) as u8
+                    ]
+                };
+                self.output.write(pixel, out);
+            }
         }
 
-        if self.chunks_read == self.chunk_count {
-            let mut padding = [0; 4];
-            self.reader.read_exact(&mut padding)?;
-            self.chunks_read += 1;
-            if padding != QoiConsts::PADDING {
-                return Err(DecoderError::InvalidPadding(padding));
+        self.finish_pixels()
+    }
+
+    /// Decodes the remaining image one row at a time into a single reused buffer.
+    ///
+    /// Rows come out top-down, so this fails with [`DecoderError::FlipUnsupported`] when flipping.
+    pub fn rows(&mut self) -> Rows<'_, R> {
+        let row = vec![0; self.state.header.width as usize * self.output.bytes_per_pixel() as usize];
+        Rows { decoder: self, row, done: false }
+    }
+
+    /// Decodes the remaining image one RGBA pixel at a time, ignoring the output format.
+    ///
+    /// Pixels come out top-down, so this fails with [`DecoderError::FlipUnsupported`] when flipping.
+    pub fn pixels(&mut self) -> Pixels<'_, R> {
+        Pixels { decoder: self, done: false }
+    }
+
+    /// Gives back the reader along with the bytes already taken from it that the decoder hasn't used, which come
+    /// before whatever is still in the reader. Chaining the two continues the stream where decoding stopped.
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        self.reader.into_parts()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader.inner
+    }
+
+    /// Reading from the inner reader directly skips bytes the decoder hasn't seen yet.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader.inner
+    }
+
+    /// Bytes already taken from the reader while detecting the version that the decoder hasn't used yet.
+    pub fn buffer(&self) -> &[u8] {
+        self.reader.buffer()
+    }
+
+    /// Moves on to the next image in the same stream, skipping whatever is left of the current one.
+    ///
+    /// Returns `false` if the stream ends cleanly where another header would start. The index and run state start
+    /// over and the version is detected again unless one was forced. [`DecodeMode::Strict`] still reports the next
+    /// image as [`DecoderError::TrailingData`] when decoding the end of the current one, but nothing is lost.
+    pub fn next_image(&mut self) -> Result<bool, DecoderError> {
+        while self.state.chunks_read < self.state.chunk_count {
+            self.advance()?;
+        }
+        match self.finish_pixels() {
+            Ok(()) | Err(DecoderError::TrailingData { .. }) => {},
+            Err(e) => return Err(e)
+        }
+
+        match self.read_header().map_err(|e| e.positioned(self.header_position()))? {
+            Some(header) => self.start(header)?,
+            None => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    /// Reads the header of the next image, or `None` if the stream ends cleanly before it.
+    fn read_header(&mut self) -> Result<Option<QoiHeader>, DecoderError> {
+        let mut bytes = [0; QoiHeader::SIZE];
+        if self.reader.read(&mut bytes[..1])? == 0 {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut bytes[1..])?;
+
+        Ok(Some(QoiHeader::from_bytes(&bytes)?))
+    }
+
+    /// Where the header of the image about to start sits, for errors about it.
+    fn header_position(&self) -> StreamPosition {
+        StreamPosition { offset: self.state.offset, ..StreamPosition::default() }
+    }
+
+    /// Prepares to decode the image behind `header`, probing for its version unless one was forced.
+    fn start(&mut self, header: QoiHeader) -> Result<(), DecoderError> {
+        let options = self.options;
+        let output = options.output.unwrap_or_else(|| PixelFormat::native(header.channels));
+        let chunk_count = options.limits.check(&header, output.bytes_per_pixel())
+            .map_err(|e| e.positioned(self.header_position()))?;
+
+        let version = match options.version {
+            Some(version) => version,
+            None => {
+                let mut probe = Vec::new();
+                let mut detector = Detector::new(header.pixel_count(), header.color_space);
+                let version = loop {
+                    if let Some(version) = detector.version() {
+                        break version;
+                    }
+
+                    let mut byte = [0];
+                    let read = match probe.len() {
+                        QoiConsts::PROBE_LENGTH => 0,
+                        _ => self.reader.read(&mut byte)
+                            .map_err(|e| DecoderError::from(e).positioned(self.header_position()))?
+                    };
+                    if read == 0 {
+                        break detector.guess();
+                    }
+
+                    probe.push(byte[0]);
+                    detector.push(byte[0]);
+                };
+                log::debug!("Detected {:?} after probing {} bytes", version, probe.len());
+                self.reader.unread(probe);
+                version
             }
+        };
+
+        options.mode.check_color_space(&header, version).map_err(|e| e.positioned(self.header_position()))?;
+        let (color_space, conversion) =
+            ColorConversion::resolve(ColorSpace::from_byte(header.color_space, version), options.color_space);
+
+        self.output = output;
+        self.color_space = color_space;
+        self.read_buf.clear();
+        self.read_pos = 0;
+        let offset = self.state.offset + QoiHeader::SIZE as u64;
+        self.state = DecodeState::new(header, options.mode, conversion, chunk_count, version, offset);
+
+        Ok(())
+    }
+
+    /// Decodes as many whole pixels as fit in `buf` without checking the end of the stream.
+    fn decode_pixels(&mut self, buf: &mut [u8]) -> Result<usize, DecoderError> {
+        let bytes_per_pixel = self.output.bytes_per_pixel() as usize;
+
+        let mut read = 0;
+        for out in buf.chunks_exact_mut(bytes_per_pixel).take(self.state.remaining()) {
+            self.advance()?;
+            self.output.write(self.state.output_pixel(), out);
+            read += bytes_per_pixel;
         }
 
         Ok(read)
     }
+
+    fn advance(&mut self) -> Result<(), DecoderError> {
+        self.state.advance(&mut self.reader)
+    }
+
+    fn finish_pixels(&mut self) -> Result<(), DecoderError> {
+        self.state.finish_pixels(&mut self.reader)
+    }
+
+    /// Streaming can only hand out rows in stream order, so it refuses to run when flipping.
+    pub(crate) fn check_streaming(&self) -> Result<(), DecoderError> {
+        match self.options.flip_vertical {
+            true => Err(DecoderError::FlipUnsupported),
+            false => Ok(())
+        }
+    }
+
+    fn destination_row(&self, y: usize, height: usize) -> usize {
+        match self.options.flip_vertical {
+            true => height - 1 - y,
+            false => y
+        }
+    }
+}
+
+/// Borrowing row reader returned by [`QoiDecoder::rows`].
+///
+/// Each row is only valid until the next call to [`Rows::next`], so this can't be an [`Iterator`].
+pub struct Rows<'a, R> {
+    decoder: &'a mut QoiDecoder<R>,
+    row: Vec<u8>,
+    done: bool
+}
+
+impl<'a, R: io::Read> Rows<'a, R> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Result<&[u8], DecoderError>> {
+        if self.done {
+            return None;
+        }
+
+        if self.decoder.state.chunks_read >= self.decoder.state.chunk_count {
+            self.done = true;
+            return self.decoder.finish_pixels().err().map(Err);
+        }
+
+        match self.decoder.check_streaming().and_then(|_| self.decoder.decode_pixels(&mut self.row)) {
+            Ok(read) => Some(Ok(&self.row[..read])),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Pixel iterator returned by [`QoiDecoder::pixels`].
+pub struct Pixels<'a, R> {
+    decoder: &'a mut QoiDecoder<R>,
+    done: bool
+}
+
+impl<'a, R: io::Read> Iterator for Pixels<'a, R> {
+    type Item = Result<[u8; 4], DecoderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.decoder.state.chunks_read >= self.decoder.state.chunk_count {
+            self.done = true;
+            return self.decoder.finish_pixels().err().map(Err);
+        }
+
+        let result = self.decoder.check_streaming().and_then(|_| self.decoder.advance());
+        self.done = result.is_err();
+        Some(result.map(|_| self.decoder.state.output_pixel()))
+    }
+}
+
+// Bytes decoded at a time to refill the buffer behind `BufRead`.
+#[cfg(feature = "std")]
+const BUFFER_LENGTH: usize = 4096;
+
+/// Streams the decoded image as bytes in the output format.
+///
+/// Reads may end partway through a pixel; the rest of it is kept for the next call. Mixing this with the
+/// other decode methods skips whatever was buffered. Rows come out top-down, so reads fail when flipping.
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for QoiDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_streaming()?;
+        if self.read_pos == self.read_buf.len() && buf.len() >= self.output.bytes_per_pixel() as usize
+            && self.state.remaining() > 0 {
+            return Ok(self.decode_pixels(buf)?);
+        }
+
+        let available = io::BufRead::fill_buf(self)?;
+        let amt = core::cmp::min(available.len(), buf.len());
+        buf[..amt].copy_from_slice(&available[..amt]);
+        io::BufRead::consume(self, amt);
+        Ok(amt)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::BufRead for QoiDecoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.check_streaming()?;
+        if self.read_pos == self.read_buf.len() {
+            let bytes_per_pixel = self.output.bytes_per_pixel() as usize;
+            let pixels = core::cmp::min(self.state.remaining(), BUFFER_LENGTH / bytes_per_pixel);
+
+            let mut read_buf = core::mem::take(&mut self.read_buf);
+            read_buf.resize(pixels * bytes_per_pixel, 0);
+            // The end of the stream is only checked once every pixel has been handed out.
+            let result = match pixels {
+                0 => self.finish_pixels().map(|_| 0),
+                _ => self.decode_pixels(&mut read_buf)
+            };
+            match result {
+                Ok(read) => read_buf.truncate(read),
+                Err(_) => read_buf.clear()
+            }
+
+            self.read_buf = read_buf;
+            self.read_pos = 0;
+            result?;
+        }
+
+        Ok(&self.read_buf[self.read_pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.read_pos = core::cmp::min(self.read_pos + amt, self.read_buf.len());
+    }
 }
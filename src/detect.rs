@@ -0,0 +1,261 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(not(feature = "std"))]
+use crate::io;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::{FormatVersion, QoiChunk, ReadQoiChunk, consts::QoiConsts, decoder::{PeekEnd, PixelState}};
+
+/// Replays the bytes consumed while probing before handing reads back to the inner reader.
+pub(crate) struct Replay<R> {
+    buf: Vec<u8>,
+    pos: usize,
+    pub(crate) inner: R
+}
+
+impl<R> Replay<R> {
+    pub(crate) fn new(buf: Vec<u8>, inner: R) -> Self {
+        Replay { buf, pos: 0, inner }
+    }
+
+    /// Puts `bytes` back in front of anything still waiting to be replayed.
+    pub(crate) fn unread(&mut self, mut bytes: Vec<u8>) {
+        bytes.extend_from_slice(&self.buf[self.pos..]);
+        self.buf = bytes;
+        self.pos = 0;
+    }
+
+    pub(crate) fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// The inner reader and the bytes still waiting to be replayed in front of it.
+    pub(crate) fn into_parts(mut self) -> (R, Vec<u8>) {
+        self.buf.drain(..self.pos);
+        (self.inner, self.buf)
+    }
+}
+
+impl<R: io::Read> io::Read for Replay<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            return self.inner.read(buf);
+        }
+
+        let amt = core::cmp::min(buf.len(), self.buf.len() - self.pos);
+        buf[..amt].copy_from_slice(&self.buf[self.pos..self.pos + amt]);
+        self.pos += amt;
+        if self.pos == self.buf.len() {
+            self.buf = Vec::new();
+            self.pos = 0;
+        }
+        Ok(amt)
+    }
+}
+
+impl<R: io::Read> PeekEnd for Replay<R> {
+    fn at_end(&mut self) -> io::Result<bool> {
+        let mut byte = [0];
+        if io::Read::read(self, &mut byte)? == 0 {
+            return Ok(true);
+        }
+
+        self.unread(vec![byte[0]]);
+        Ok(false)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Pending,
+    Invalid,
+    Complete
+}
+
+/// Follows the stream as if it were written in `version`, counting anything a real encoder wouldn't emit.
+struct Hypothesis {
+    state: PixelState,
+    written: u64,
+
+    chunk: [u8; QoiConsts::CHUNK_LENGTH_MAX],
+    chunk_len: usize,
+    chunk_pos: usize,
+
+    pixels: u64,
+    trailer_pos: usize,
+    short_run: bool,
+
+    anomalies: u32,
+    verdict: Verdict
+}
+
+impl Hypothesis {
+    fn new(version: FormatVersion) -> Self {
+        Hypothesis {
+            state: PixelState::new(version),
+            written: 0,
+
+            chunk: [0; QoiConsts::CHUNK_LENGTH_MAX],
+            chunk_len: 0,
+            chunk_pos: 0,
+
+            pixels: 0,
+            trailer_pos: 0,
+            short_run: false,
+
+            anomalies: 0,
+            verdict: Verdict::Pending
+        }
+    }
+
+    fn push(&mut self, byte: u8, total_pixels: u64) {
+        if self.verdict != Verdict::Pending {
+            return;
+        }
+
+        if self.pixels == total_pixels {
+            let trailer: &[u8] = match self.state.version {
+                FormatVersion::Draft => &QoiConsts::PADDING,
+                FormatVersion::V1 => &QoiConsts::END_MARKER
+            };
+
+            if byte != trailer[self.trailer_pos] {
+                self.verdict = Verdict::Invalid;
+            } else {
+                self.trailer_pos += 1;
+                if self.trailer_pos == trailer.len() {
+                    self.verdict = Verdict::Complete;
+                }
+            }
+            return;
+        }
+
+        if self.chunk_pos == 0 {
+            self.chunk_len = QoiChunk::len_from_first_byte(byte, self.state.version);
+        }
+        self.chunk[self.chunk_pos] = byte;
+        self.chunk_pos += 1;
+        if self.chunk_pos < self.chunk_len {
+            return;
+        }
+        self.chunk_pos = 0;
+
+        match (&self.chunk[..self.chunk_len]).read_qoi_chunk(self.state.version) {
+            Ok(chunk) => self.inspect(chunk, total_pixels),
+            Err(_) => self.verdict = Verdict::Invalid
+        }
+    }
+
+    fn in_trailer(&self, total_pixels: u64) -> bool {
+        self.pixels == total_pixels && self.chunk_pos == 0
+    }
+
+    fn inspect(&mut self, chunk: QoiChunk, total_pixels: u64) {
+        let previous_pixel = self.state.pixel;
+        let run_max = match chunk {
+            QoiChunk::Run16(run) => Some(run == QoiChunk::RUN_16_MAX),
+            QoiChunk::Run(run) => Some(run == QoiConsts::V1_RUN_MAX - 1),
+            QoiChunk::Run8(_) => Some(false),
+            QoiChunk::Index(pos) => {
+                // Only the all-zero pixel hashes to the first slot before anything was written there.
+                if pos != 0 && self.written & (1 << pos) == 0 {
+                    self.anomalies += 1;
+                }
+                None
+            },
+            QoiChunk::Color(None, None, None, None) => {
+                self.anomalies += 1;
+                None
+            },
+            _ => None
+        };
+        let is_index = matches!(chunk, QoiChunk::Index(_));
+
+        self.state.apply(chunk);
+
+        if let Some(run_max) = run_max {
+            if self.short_run {
+                self.anomalies += 1;
+            }
+            self.short_run = !run_max;
+            self.pixels += 1 + self.state.run as u64;
+            self.state.run = 0;
+        } else {
+            if !is_index && self.state.pixel == previous_pixel {
+                self.anomalies += 1;
+            }
+            self.short_run = false;
+            self.written |= 1 << self.state.index_position();
+            self.pixels += 1;
+        }
+
+        if self.pixels > total_pixels {
+            self.verdict = Verdict::Invalid;
+        }
+    }
+}
+
+/// Classifies a stream as draft or QOI 1.0 from the header's color space byte and a bounded probe of the chunks.
+pub(crate) struct Detector {
+    total_pixels: u64,
+    version: Option<FormatVersion>,
+
+    draft: Hypothesis,
+    v1: Hypothesis
+}
+
+impl Detector {
+    pub(crate) fn new(total_pixels: u64, color_space: u8) -> Self {
+        // QOI 1.0 only defines 0 and 1, while the draft uses the low nibble as per-channel flags.
+        let version = if (QoiConsts::V1_LINEAR + 1..=QoiConsts::LINEAR).contains(&color_space) {
+            Some(FormatVersion::Draft)
+        } else {
+            None
+        };
+
+        Detector {
+            total_pixels,
+            version,
+
+            draft: Hypothesis::new(FormatVersion::Draft),
+            v1: Hypothesis::new(FormatVersion::V1)
+        }
+    }
+
+    pub(crate) fn version(&self) -> Option<FormatVersion> {
+        self.version
+    }
+
+    pub(crate) fn push(&mut self, byte: u8) {
+        self.draft.push(byte, self.total_pixels);
+        self.v1.push(byte, self.total_pixels);
+
+        // The draft padding is a prefix of the 1.0 end marker, so a completed draft isn't conclusive
+        // while the 1.0 reading is still matching its own end marker.
+        self.version = match (self.draft.verdict, self.v1.verdict) {
+            (Verdict::Complete, Verdict::Pending) if self.v1.in_trailer(self.total_pixels) => None,
+            (_, Verdict::Complete) | (Verdict::Invalid, Verdict::Pending) => Some(FormatVersion::V1),
+            (Verdict::Complete, _) | (Verdict::Pending, Verdict::Invalid) => Some(FormatVersion::Draft),
+            (Verdict::Invalid, Verdict::Invalid) => Some(self.guess()),
+            (Verdict::Pending, Verdict::Pending) => None
+        };
+    }
+
+    /// Whether a whole draft image has been seen, which only a 1.0 end marker still being matched could overrule.
+    #[cfg(feature = "async")]
+    pub(crate) fn draft_complete(&self) -> bool {
+        self.draft.verdict == Verdict::Complete
+    }
+
+    /// Picks the more plausible version when the probe ran out before either was ruled out.
+    pub(crate) fn guess(&self) -> FormatVersion {
+        if self.draft.verdict == Verdict::Complete || self.draft.anomalies < self.v1.anomalies {
+            FormatVersion::Draft
+        } else {
+            FormatVersion::V1
+        }
+    }
+}
@@ -4,19 +4,143 @@ use std::io;
 #[cfg(not(feature = "std"))]
 use crate::io;
 
-use byteorder::BigEndian;
-#[cfg(feature = "std")]
-use byteorder::WriteBytesExt;
+use crate::{
+    ColorSpace, EncodeStats, EncoderError, FormatVersion, PixelFormat, QoiChunk, QoiHeader, WriteQoiChunk, consts::*,
+    srgb::ColorConversion
+};
 
-use crate::{ColorSpace, EncoderError, QoiChunk, WriteQoiChunk, consts::*};
+/// Options for a [`QoiEncoder`], also used by [`QoiEncoder::encode_image`] to fill in the image's shape.
+///
+/// Methods that take the channel count and color space as arguments reject values that differ from ones set here.
+#[derive(Debug, Clone, Copy)]
+pub struct QoiEncoderBuilder {
+    pub(crate) version: FormatVersion,
+    pub(crate) channels: Option<u8>,
+    pub(crate) color_space: Option<ColorSpace>,
+    pub(crate) input: Option<PixelFormat>,
+    pub(crate) input_color_space: Option<ColorSpace>,
+    pub(crate) flip_vertical: bool,
+    pub(crate) dry_run: bool,
+    #[cfg(all(feature = "image", feature = "std"))]
+    pub(crate) dither: bool
+}
 
-pub struct QoiEncoder<'a, W: 'a> {
-    writer: &'a mut W,
+impl Default for QoiEncoderBuilder {
+    fn default() -> Self {
+        QoiEncoderBuilder {
+            version: FormatVersion::Draft,
+            channels: None,
+            color_space: None,
+            input: None,
+            input_color_space: None,
+            flip_vertical: false,
+            dry_run: false,
+            #[cfg(all(feature = "image", feature = "std"))]
+            dither: false
+        }
+    }
 }
 
-impl<'a, W: 'a + io::Write> QoiEncoder<'a, W> {
-    pub fn new(writer: &'a mut W) -> Self {
-        Self { writer }
+impl QoiEncoderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Channels stored by [`QoiEncoder::encode_image`] and `image::ImageEncoder`. The first stores 4 unless set,
+    /// the second follows the color type.
+    pub fn channels(mut self, channels: u8) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Color space written by [`QoiEncoder::encode_image`] and `image::ImageEncoder`, sRGB unless set.
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = Some(color_space);
+        self
+    }
+
+    /// Reads the source as `format` instead of the RGB or RGBA layout implied by the channel count.
+    ///
+    /// The channel count still decides what is stored, so alpha is dropped when encoding a 4 byte format as
+    /// 3 channels.
+    pub fn input_format(mut self, format: PixelFormat) -> Self {
+        self.input = Some(format);
+        self
+    }
+
+    /// Treats the source pixels as `color_space`, converting each channel between sRGB and linear encoding
+    /// to match the color space being written.
+    pub fn input_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.input_color_space = Some(color_space);
+        self
+    }
+
+    /// Reads the source rows bottom-up, for buffers laid out the way OpenGL and BMP expect.
+    pub fn flip_vertical(mut self, flip: bool) -> Self {
+        self.flip_vertical = flip;
+        self
+    }
+
+    /// Encodes without writing anything, for when only the returned [`EncodeStats`] are wanted.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Dithers 16-bit input down to 8 bits when encoding through `image::ImageEncoder` instead of rounding.
+    #[cfg(all(feature = "image", feature = "std"))]
+    pub fn dithering(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn build<W: io::Write>(self, writer: W) -> QoiEncoder<W> {
+        QoiEncoder { writer, options: self }
+    }
+}
+
+pub struct QoiEncoder<W> {
+    writer: W,
+    pub(crate) options: QoiEncoderBuilder
+}
+
+impl QoiEncoder<()> {
+    pub fn builder() -> QoiEncoderBuilder {
+        QoiEncoderBuilder::new()
+    }
+}
+
+impl<W: io::Write> QoiEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        QoiEncoderBuilder::new().build(writer)
+    }
+
+    pub fn new_with_version(writer: W, version: FormatVersion) -> Self {
+        QoiEncoderBuilder::new().version(version).build(writer)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Encodes `buf` with the channel count and color space the encoder was built with.
+    pub fn encode_image(&mut self, buf: &[u8], width: u32, height: u32) -> Result<EncodeStats, EncoderError> {
+        let channels = self.options.channels.unwrap_or(4);
+        let color_space = self.options.color_space.unwrap_or(ColorSpace::Srgb);
+        self.encode(buf, width, height, channels, color_space)
     }
 
     pub fn encode(
@@ -26,76 +150,398 @@ impl<'a, W: 'a + io::Write> QoiEncoder<'a, W> {
         height: u32,
         channels: u8,
         color_space: ColorSpace
-    ) -> Result<(), EncoderError> {
-        self.writer.write_all(&QoiConsts::MAGIC)?;
-        self.writer.write_u32::<BigEndian>(width)?;
-        self.writer.write_u32::<BigEndian>(height)?;
-        self.writer.write_u8(channels)?;
-        self.writer.write_u8(color_space.into())?;
-
-        let channels = channels as usize;
-        let pixels = buf.chunks_exact(channels);
-        let pixels_len = pixels.len();
-
-        let mut pixel = [0, 0, 0, 255];
-        let mut previous_pixel = pixel;
-        let mut index = [[0; 4]; QoiConsts::INDEX_SIZE];
-        let mut run: u16 = 0;
-
-        for (pixel_index, current) in pixels.enumerate() {
-            pixel[..channels].copy_from_slice(current);
-
-            if pixel == previous_pixel {
-                run += 1;
-            }
+    ) -> Result<EncodeStats, EncoderError> {
+        self.options.check_options(channels, color_space)?;
+        let pixels = self.options.pixels(buf, width, height, channels)?;
+        self.encode_pixels(pixels, width as u64 * height as u64, width, height, channels, color_space)
+    }
+
+    /// Encodes the `dimensions` sized rectangle at `origin` out of a larger image whose rows start `stride` bytes apart.
+    pub fn encode_region(
+        &mut self,
+        buf: &[u8],
+        stride: usize,
+        origin: (u32, u32),
+        dimensions: (u32, u32),
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<EncodeStats, EncoderError> {
+        self.options.check_options(channels, color_space)?;
+        let pixels = self.options.region_pixels(buf, stride, origin, dimensions, channels)?;
+
+        let (width, height) = dimensions;
+        self.encode_pixels(pixels, width as u64 * height as u64, width, height, channels, color_space)
+    }
+
+    /// Encodes pixels packed as `0xAARRGGBB`, as used by software framebuffers.
+    pub fn encode_packed(
+        &mut self,
+        buf: &[u32],
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<EncodeStats, EncoderError> {
+        self.options.check_options(channels, color_space)?;
+        let pixels = self.options.packed_pixels(buf, width, height, channels)?;
+        self.encode_pixels(pixels, width as u64 * height as u64, width, height, channels, color_space)
+    }
+
+    pub(crate) fn encode_pixels<I: Iterator<Item = [u8; 4]>>(
+        &mut self,
+        pixels: I,
+        count: u64,
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<EncodeStats, EncoderError> {
+        let (header, pixels) = self.options.prepare(pixels, width, height, channels, color_space)?;
+
+        match self.options.dry_run {
+            true => encode_into(&mut Discard, self.options.version, &header, pixels, count),
+            false => encode_into(&mut self.writer, self.options.version, &header, pixels, count)
+        }
+    }
+
+    pub(crate) fn write_header(&mut self, width: u32, height: u32, channels: u8, color_space: ColorSpace) -> Result<(), EncoderError> {
+        let color_space = color_space.to_byte(self.options.version)
+            .ok_or(EncoderError::UnsupportedColorSpace(color_space))?;
+
+        QoiHeader::new(width, height, channels, color_space).write_to(&mut self.writer)
+    }
+
+    pub(crate) fn write_trailer(&mut self) -> Result<(), EncoderError> {
+        self.writer.write_all(self.options.version.trailer())?;
+        Ok(())
+    }
+
+    pub(crate) fn version(&self) -> FormatVersion {
+        self.options.version
+    }
+}
+
+// The pixel pipeline behind both the blocking and async encoders.
+impl QoiEncoderBuilder {
+    /// Rejects a channel count or color space that contradicts the one the encoder was built with.
+    pub(crate) fn check_options(&self, channels: u8, color_space: ColorSpace) -> Result<(), EncoderError> {
+        match (self.channels, self.color_space) {
+            (Some(expected), _) if expected != channels =>
+                Err(EncoderError::ChannelsMismatch { expected, actual: channels }),
+            (_, Some(expected)) if expected != color_space =>
+                Err(EncoderError::ColorSpaceMismatch { expected, actual: color_space }),
+            _ => Ok(())
+        }
+    }
+
+    pub(crate) fn row_order(&self, height: u32) -> impl Iterator<Item = usize> {
+        let (flip, height) = (self.flip_vertical, height as usize);
+        (0..height).map(move |row| if flip { height - 1 - row } else { row })
+    }
+
+    /// Reads a buffer holding exactly one image in encoding order.
+    pub(crate) fn pixels<'a>(
+        &self,
+        buf: &'a [u8],
+        width: u32,
+        height: u32,
+        channels: u8
+    ) -> Result<impl Iterator<Item = [u8; 4]> + 'a, EncoderError> {
+        let bytes_per_pixel = self.input.unwrap_or_else(|| PixelFormat::native(channels)).bytes_per_pixel() as usize;
+        check_buffer(width, height, channels, bytes_per_pixel, buf.len())?;
 
-            if run > 0 && (run == 0x2020 || pixel != previous_pixel || pixel_index == pixels_len - 1) {
-                self.writer.write_qoi_chunk(
-                    if run < 33 {
-                        run -= 1;
-                        QoiChunk::Run8(run as u8)
-                    } else {
-                        run -= 33;
-                        QoiChunk::Run16(run)
-                    }
-                )?;
-
-                run = 0;
+        self.region_pixels(buf, width as usize * bytes_per_pixel, (0, 0), (width, height), channels)
+    }
+
+    /// Reads the `dimensions` sized rectangle at `origin` in encoding order.
+    pub(crate) fn region_pixels<'a>(
+        &self,
+        buf: &'a [u8],
+        stride: usize,
+        origin: (u32, u32),
+        dimensions: (u32, u32),
+        channels: u8
+    ) -> Result<impl Iterator<Item = [u8; 4]> + 'a, EncoderError> {
+        let input = self.input.unwrap_or_else(|| PixelFormat::native(channels));
+        let bytes_per_pixel = input.bytes_per_pixel() as usize;
+        let (x, y) = (origin.0 as usize, origin.1 as usize);
+        let (width, height) = dimensions;
+        check_dimensions(width, height, channels)?;
+
+        // Offsets that overflow can't fit in any buffer, so they saturate and fail the checks below.
+        let (start, end) = x.checked_mul(bytes_per_pixel)
+            .and_then(|start| Some((start, start.checked_add((width as usize).checked_mul(bytes_per_pixel)?)?)))
+            .unwrap_or((usize::MAX, usize::MAX));
+        if stride < end {
+            return Err(EncoderError::InvalidStride { stride, row_len: end });
+        }
+
+        let expected = y.checked_add(height as usize - 1)
+            .and_then(|last_row| stride.checked_mul(last_row))
+            .and_then(|len| len.checked_add(end))
+            .unwrap_or(usize::MAX);
+        if buf.len() < expected {
+            return Err(EncoderError::BufferTooSmall { expected, actual: buf.len() });
+        }
+
+        Ok(self.row_order(height)
+            .flat_map(move |row| buf[(y + row) * stride + start..(y + row) * stride + end].chunks_exact(bytes_per_pixel))
+            .map(move |pixel| input.read(pixel)))
+    }
+
+    /// Reads pixels packed as `0xAARRGGBB` in encoding order.
+    pub(crate) fn packed_pixels<'a>(
+        &self,
+        buf: &'a [u32],
+        width: u32,
+        height: u32,
+        channels: u8
+    ) -> Result<impl Iterator<Item = [u8; 4]> + 'a, EncoderError> {
+        check_buffer(width, height, channels, 4, buf.len() * 4)?;
+
+        let width = width as usize;
+        Ok(self.row_order(height).flat_map(move |row| buf[row * width..(row + 1) * width].iter().map(|&pixel| {
+            let [a, r, g, b] = pixel.to_be_bytes();
+            [r, g, b, a]
+        })))
+    }
+
+    /// Builds the header for an image and converts `pixels` to what gets stored.
+    pub(crate) fn prepare<I: Iterator<Item = [u8; 4]>>(
+        &self,
+        pixels: I,
+        width: u32,
+        height: u32,
+        channels: u8,
+        color_space: ColorSpace
+    ) -> Result<(QoiHeader, impl Iterator<Item = [u8; 4]>), EncoderError> {
+        let color_space_byte = color_space.to_byte(self.version)
+            .ok_or(EncoderError::UnsupportedColorSpace(color_space))?;
+        let header = QoiHeader::new(width, height, channels, color_space_byte);
+        header.validate()?;
+
+        let conversion = self.input_color_space
+            .map_or(ColorConversion::IDENTITY, |input| ColorConversion::new(input, color_space));
+        let pixels = pixels.map(move |pixel| {
+            let mut pixel = conversion.apply(pixel);
+            if channels == 3 {
+                pixel[3] = 255;
             }
+            pixel
+        });
+
+        Ok((header, pixels))
+    }
+}
+
+/// Rejects images that can't be encoded, before anything is written.
+pub(crate) fn check_dimensions(width: u32, height: u32, channels: u8) -> Result<(), EncoderError> {
+    if !(QoiConsts::CHANNELS_MIN..=QoiConsts::CHANNELS_MAX).contains(&channels) {
+        return Err(EncoderError::InvalidChannelCount(channels));
+    }
+    if width == 0 || height == 0 {
+        return Err(EncoderError::InvalidDimensions { width, height });
+    }
+
+    Ok(())
+}
+
+/// Also requires `len` bytes to hold exactly one image of `bytes_per_pixel` sized pixels.
+pub(crate) fn check_buffer(
+    width: u32,
+    height: u32,
+    channels: u8,
+    bytes_per_pixel: usize,
+    len: usize
+) -> Result<(), EncoderError> {
+    check_dimensions(width, height, channels)?;
+
+    let expected = (width as usize).checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+        .ok_or(EncoderError::InvalidDimensions { width, height })?;
+    if len != expected {
+        return Err(EncoderError::BufferSizeMismatch { expected, actual: len });
+    }
+
+    Ok(())
+}
+
+fn encode_into<O: io::Write, I: Iterator<Item = [u8; 4]>>(
+    writer: &mut O,
+    version: FormatVersion,
+    header: &QoiHeader,
+    pixels: I,
+    count: u64
+) -> Result<EncodeStats, EncoderError> {
+    header.write_to(writer)?;
+
+    let mut pixel_encoder = PixelEncoder::new(version, count);
+    for pixel in pixels {
+        pixel_encoder.push(writer, pixel)?;
+    }
+
+    writer.write_all(version.trailer())?;
+    Ok(pixel_encoder.finish(header.channels))
+}
+
+// Swallows everything written to it, for dry runs.
+struct Discard;
+
+impl io::Write for Discard {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    #[cfg(feature = "std")]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) struct PixelEncoder {
+    version: FormatVersion,
+    remaining: u64,
+    stats: EncodeStats,
+
+    run: u16,
+    previous_pixel: [u8; 4],
+    index: [[u8; 4]; QoiConsts::INDEX_SIZE]
+}
+
+impl PixelEncoder {
+    pub(crate) fn new(version: FormatVersion, pixels: u64) -> Self {
+        PixelEncoder {
+            version,
+            remaining: pixels,
+            stats: EncodeStats::default(),
+
+            run: 0,
+            previous_pixel: [0, 0, 0, 255],
+            index: [[0; 4]; QoiConsts::INDEX_SIZE]
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push<W: io::Write>(&mut self, writer: &mut W, pixel: [u8; 4]) -> Result<(), EncoderError> {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.stats.pixels += 1;
+        match self.version {
+            FormatVersion::Draft => self.push_draft(writer, pixel),
+            FormatVersion::V1 => self.push_v1(writer, pixel)
+        }
+    }
+
+    /// Fills in the totals, given how many channels are stored per pixel.
+    pub(crate) fn finish(mut self, channels: u8) -> EncodeStats {
+        self.stats.raw_bytes = self.stats.pixels * channels as u64;
+        self.stats.encoded_bytes += (QoiHeader::SIZE + self.version.trailer().len()) as u64;
+        self.stats
+    }
+
+    #[inline]
+    fn write_chunk<W: io::Write>(&mut self, writer: &mut W, chunk: QoiChunk) -> Result<(), EncoderError> {
+        let wrote = writer.write_qoi_chunk(chunk)?;
+        self.stats.record(&chunk, wrote);
+        self.stats.encoded_bytes += wrote as u64;
+        Ok(())
+    }
+
+    #[inline]
+    fn push_draft<W: io::Write>(&mut self, writer: &mut W, pixel: [u8; 4]) -> Result<(), EncoderError> {
+        let previous_pixel = self.previous_pixel;
+
+        if pixel == previous_pixel {
+            self.run += 1;
+        }
 
-            if pixel != previous_pixel {
-                let index_pos = QoiConsts::pixel_hash(&pixel);
-
-                self.writer.write_qoi_chunk(
-                    if index[index_pos] == pixel {
-                        QoiChunk::Index(index_pos as u8)
-                    } else {
-                        index[index_pos].copy_from_slice(&pixel);
-
-                        let r = pixel[0].wrapping_sub(previous_pixel[0]).wrapping_add(16);
-                        let g = pixel[1].wrapping_sub(previous_pixel[1]).wrapping_add(16);
-                        let b = pixel[2].wrapping_sub(previous_pixel[2]).wrapping_add(16);
-                        let a = pixel[3].wrapping_sub(previous_pixel[3]).wrapping_add(16);
-
-                        match (r, g, b, a) {
-                            (14..=17, 14..=17, 14..=17, 16) => QoiChunk::Diff8(r - 14, g - 14, b - 14),
-                            (0..=31, 8..=23, 8..=23, 16) => QoiChunk::Diff16(r, g - 8, b - 8),
-                            (0..=31, 0..=31, 0..=31, 0..=31) => QoiChunk::Diff24(r, g, b, a),
-                            _ => QoiChunk::Color(
-                                if r != 16 { Some(pixel[0]) } else { None },
-                                if g != 16 { Some(pixel[1]) } else { None },
-                                if b != 16 { Some(pixel[2]) } else { None },
-                                if a != 16 { Some(pixel[3]) } else { None }
-                            )
-                        }
-                    }
-                )?;
-
-                previous_pixel[..channels].copy_from_slice(&pixel);
+        if self.run > 0 && (self.run == 0x2020 || pixel != previous_pixel || self.remaining == 0) {
+            let chunk = if self.run < 33 {
+                QoiChunk::Run8((self.run - 1) as u8)
+            } else {
+                QoiChunk::Run16(self.run - 33)
+            };
+            self.write_chunk(writer, chunk)?;
+
+            self.run = 0;
+        }
+
+        if pixel != previous_pixel {
+            let index_pos = QoiConsts::pixel_hash(&pixel);
+
+            let chunk = if self.index[index_pos] == pixel {
+                QoiChunk::Index(index_pos as u8)
+            } else {
+                self.index[index_pos].copy_from_slice(&pixel);
+
+                let r = pixel[0].wrapping_sub(previous_pixel[0]).wrapping_add(16);
+                let g = pixel[1].wrapping_sub(previous_pixel[1]).wrapping_add(16);
+                let b = pixel[2].wrapping_sub(previous_pixel[2]).wrapping_add(16);
+                let a = pixel[3].wrapping_sub(previous_pixel[3]).wrapping_add(16);
+
+                match (r, g, b, a) {
+                    (14..=17, 14..=17, 14..=17, 16) => QoiChunk::Diff8(r - 14, g - 14, b - 14),
+                    (0..=31, 8..=23, 8..=23, 16) => QoiChunk::Diff16(r, g - 8, b - 8),
+                    (0..=31, 0..=31, 0..=31, 0..=31) => QoiChunk::Diff24(r, g, b, a),
+                    _ => QoiChunk::Color(
+                        if r != 16 { Some(pixel[0]) } else { None },
+                        if g != 16 { Some(pixel[1]) } else { None },
+                        if b != 16 { Some(pixel[2]) } else { None },
+                        if a != 16 { Some(pixel[3]) } else { None }
+                    )
+                }
+            };
+            self.write_chunk(writer, chunk)?;
+
+            self.previous_pixel = pixel;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn push_v1<W: io::Write>(&mut self, writer: &mut W, pixel: [u8; 4]) -> Result<(), EncoderError> {
+        let previous_pixel = self.previous_pixel;
+
+        if pixel == previous_pixel {
+            self.run += 1;
+            if self.run == QoiConsts::V1_RUN_MAX as u16 || self.remaining == 0 {
+                self.write_chunk(writer, QoiChunk::Run((self.run - 1) as u8))?;
+                self.run = 0;
             }
+            return Ok(());
+        }
+
+        if self.run > 0 {
+            self.write_chunk(writer, QoiChunk::Run((self.run - 1) as u8))?;
+            self.run = 0;
         }
 
-        self.writer.write_all(&QoiConsts::PADDING)?;
+        let index_pos = QoiConsts::pixel_hash_v1(&pixel);
+
+        let chunk = if self.index[index_pos] == pixel {
+            QoiChunk::Index(index_pos as u8)
+        } else {
+            self.index[index_pos] = pixel;
+
+            if pixel[3] == previous_pixel[3] {
+                let r = pixel[0].wrapping_sub(previous_pixel[0]) as i8;
+                let g = pixel[1].wrapping_sub(previous_pixel[1]) as i8;
+                let b = pixel[2].wrapping_sub(previous_pixel[2]) as i8;
+                let (rg, bg) = (r.wrapping_sub(g), b.wrapping_sub(g));
+
+                match (r, g, b) {
+                    (-2..=1, -2..=1, -2..=1) => QoiChunk::Diff((r + 2) as u8, (g + 2) as u8, (b + 2) as u8),
+                    (_, -32..=31, _) if (-8..=7).contains(&rg) && (-8..=7).contains(&bg) =>
+                        QoiChunk::Luma((g + 32) as u8, (rg + 8) as u8, (bg + 8) as u8),
+                    _ => QoiChunk::Rgb(pixel[0], pixel[1], pixel[2])
+                }
+            } else {
+                QoiChunk::Rgba(pixel[0], pixel[1], pixel[2], pixel[3])
+            }
+        };
+        self.write_chunk(writer, chunk)?;
+
+        self.previous_pixel = pixel;
 
         Ok(())
     }
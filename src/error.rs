@@ -7,35 +7,168 @@ use crate::io;
 
 use core::fmt;
 
+use crate::{ColorSpace, Limit, QoiChunk};
+
+/// Where in the stream decoding failed.
+///
+/// Every error about the stream itself carries one. Errors from the header point at the start of the header with
+/// pixel zero. Errors from the chunk layer on its own leave this zeroed; the decoders and [`crate::QoiChunks`] fill
+/// it in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamPosition {
+    /// Bytes from the start of the file to the chunk or padding being read.
+    pub offset: u64,
+    /// Index of the pixel being decoded, equal to the pixel count once the pixels are done.
+    pub pixel: u64,
+    pub x: u32,
+    pub y: u32
+}
+
+impl StreamPosition {
+    pub(crate) fn new(offset: u64, pixel: u64, width: u32) -> Self {
+        let (x, y) = match width {
+            0 => (0, 0),
+            _ => ((pixel % width as u64) as u32, (pixel / width as u64) as u32)
+        };
+        StreamPosition { offset, pixel, x, y }
+    }
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum DecoderError {
-    InvalidSignature([u8; 4]),
-    InvalidChannelCount(u8),
-    InvalidChunkStart(u8),
-    InvalidPadding([u8; 4]),
-    IoError(io::Error)
+    InvalidSignature { signature: [u8; 4], position: StreamPosition },
+    InvalidChannelCount { channels: u8, position: StreamPosition },
+    InvalidChunkStart { byte: u8, position: StreamPosition },
+    InvalidPadding { padding: [u8; 4], position: StreamPosition },
+    InvalidEndMarker { end_marker: [u8; 8], position: StreamPosition },
+    InvalidColorSpace { color_space: u8, position: StreamPosition },
+    RunOverflow { overflow: usize, position: StreamPosition },
+    TrailingData { position: StreamPosition },
+    Truncated { expected_pixels: u64, decoded_pixels: u64, position: StreamPosition },
+    LimitExceeded { limit: Limit, value: u64, max: u64, position: StreamPosition },
+    InvalidStride { stride: usize, row_len: usize },
+    BufferTooSmall { expected: usize, actual: usize },
+    InvalidRegion { x: u32, y: u32, width: u32, height: u32 },
+    InvalidScale { width: u32, height: u32 },
+    DecodeStarted { decoded_pixels: u64 },
+    FlipUnsupported,
+    IoError { error: io::Error, position: StreamPosition }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeaderError {
+    InvalidChannelCount(u8)
 }
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum EncoderError {
+    InvalidChannelCount(u8),
+    UnsupportedColorSpace(ColorSpace),
+    InvalidStride { stride: usize, row_len: usize },
+    BufferTooSmall { expected: usize, actual: usize },
+    BufferSizeMismatch { expected: usize, actual: usize },
+    InvalidDimensions { width: u32, height: u32 },
+    InvalidChunk(QoiChunk),
+    ChannelsMismatch { expected: u8, actual: u8 },
+    ColorSpaceMismatch { expected: ColorSpace, actual: ColorSpace },
     IoError(io::Error)
 }
 
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TranscodeError {
+    Decoder(DecoderError),
+    Encoder(EncoderError)
+}
+
+impl DecoderError {
+    pub(crate) fn is_eof(&self) -> bool {
+        #[cfg(feature = "std")]
+        return matches!(self, DecoderError::IoError { error, .. } if error.kind() == io::ErrorKind::UnexpectedEof);
+        #[cfg(not(feature = "std"))]
+        return matches!(self, DecoderError::IoError { error: io::Error::UnexpectedEof, .. });
+    }
+
+    /// Records where the error happened, turning a bare end of file into [`DecoderError::Truncated`].
+    pub(crate) fn at(self, at: StreamPosition, expected_pixels: u64) -> Self {
+        match self {
+            e if e.is_eof() =>
+                DecoderError::Truncated { expected_pixels, decoded_pixels: at.pixel, position: at },
+            e => e.positioned(at)
+        }
+    }
+
+    /// Records where the error happened, leaving everything else as it is.
+    pub(crate) fn positioned(self, at: StreamPosition) -> Self {
+        match self {
+            DecoderError::InvalidSignature { signature, .. } =>
+                DecoderError::InvalidSignature { signature, position: at },
+            DecoderError::InvalidChannelCount { channels, .. } =>
+                DecoderError::InvalidChannelCount { channels, position: at },
+            DecoderError::InvalidChunkStart { byte, .. } => DecoderError::InvalidChunkStart { byte, position: at },
+            DecoderError::InvalidPadding { padding, .. } => DecoderError::InvalidPadding { padding, position: at },
+            DecoderError::InvalidEndMarker { end_marker, .. } =>
+                DecoderError::InvalidEndMarker { end_marker, position: at },
+            DecoderError::InvalidColorSpace { color_space, .. } =>
+                DecoderError::InvalidColorSpace { color_space, position: at },
+            DecoderError::RunOverflow { overflow, .. } => DecoderError::RunOverflow { overflow, position: at },
+            DecoderError::TrailingData { .. } => DecoderError::TrailingData { position: at },
+            DecoderError::Truncated { expected_pixels, decoded_pixels, .. } =>
+                DecoderError::Truncated { expected_pixels, decoded_pixels, position: at },
+            DecoderError::LimitExceeded { limit, value, max, .. } =>
+                DecoderError::LimitExceeded { limit, value, max, position: at },
+            DecoderError::IoError { error, .. } => DecoderError::IoError { error, position: at },
+            e => e
+        }
+    }
+}
+
+impl fmt::Display for StreamPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}, pixel {} ({}, {})", self.offset, self.pixel, self.x, self.y)
+    }
+}
+
 impl fmt::Display for DecoderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DecoderError::InvalidSignature(signature) =>
-                write!(f, "QOI header has invalid signature ({:?})", signature),
-            DecoderError::InvalidChannelCount(count) =>
-                write!(f, "QOI header has invalid channel count ({})", count),
-            DecoderError::InvalidChunkStart(start) =>
-                write!(f, "QOI chunk has an invalid start ({:X})", start),
-            DecoderError::InvalidPadding(padding) =>
-                write!(f, "QOI file has invalid padding ({:?})", padding),
-            
-            DecoderError::IoError(e) => fmt::Display::fmt(e, f),
+            DecoderError::InvalidSignature { signature, position } =>
+                write!(f, "QOI header has invalid signature ({:?}) at byte {}", signature, position.offset),
+            DecoderError::InvalidChannelCount { channels, position } =>
+                write!(f, "QOI header has invalid channel count ({}) at byte {}", channels, position.offset),
+            DecoderError::InvalidChunkStart { byte, position } =>
+                write!(f, "QOI chunk has an invalid start ({:X}) at {}", byte, position),
+            DecoderError::InvalidPadding { padding, position } =>
+                write!(f, "QOI file has invalid padding ({:?}) at {}", padding, position),
+            DecoderError::InvalidEndMarker { end_marker, position } =>
+                write!(f, "QOI file has invalid end marker ({:?}) at {}", end_marker, position),
+            DecoderError::InvalidColorSpace { color_space, position } =>
+                write!(f, "QOI header has unknown color space ({:X}) at byte {}", color_space, position.offset),
+            DecoderError::RunOverflow { overflow, position } =>
+                write!(f, "QOI run continues {} pixels past the end of the image at {}", overflow, position),
+            DecoderError::TrailingData { position } =>
+                write!(f, "QOI file has data after its padding at {}", position),
+            DecoderError::Truncated { expected_pixels, decoded_pixels, position } =>
+                write!(f, "QOI stream ends after {} of {} pixels at {}", decoded_pixels, expected_pixels, position),
+            DecoderError::LimitExceeded { limit, value, max, position } =>
+                write!(f, "QOI image at byte {} exceeds the {:?} limit ({} > {})", position.offset, limit, value, max),
+            DecoderError::InvalidStride { stride, row_len } =>
+                write!(f, "Stride of {} bytes is shorter than a row of {} bytes", stride, row_len),
+            DecoderError::BufferTooSmall { expected, actual } =>
+                write!(f, "Buffer of {} bytes is too small, {} bytes are needed", actual, expected),
+            DecoderError::InvalidRegion { x, y, width, height } =>
+                write!(f, "Region {}x{} at ({}, {}) is outside the image or already decoded", width, height, x, y),
+            DecoderError::InvalidScale { width, height } =>
+                write!(f, "Can't downscale to {}x{}", width, height),
+            DecoderError::DecodeStarted { decoded_pixels } =>
+                write!(f, "Decoding needs the whole image but {} pixels were already decoded", decoded_pixels),
+            DecoderError::FlipUnsupported =>
+                write!(f, "Flipping vertically needs the whole image decoded into one buffer"),
+
+            DecoderError::IoError { error, position } => write!(f, "{} at {}", error, position),
 
             #[allow(unreachable_patterns)]
             _ => unreachable!()
@@ -43,25 +176,57 @@ impl fmt::Display for DecoderError {
     }
 }
 
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::InvalidChannelCount(count) =>
+                write!(f, "QOI header has invalid channel count ({})", count),
+        }
+    }
+}
+
 impl fmt::Display for EncoderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            EncoderError::InvalidChannelCount(count) =>
+                write!(f, "QOI header has invalid channel count ({})", count),
+            EncoderError::UnsupportedColorSpace(color_space) =>
+                write!(f, "Color space {:?} can't be represented in this QOI version", color_space),
+            EncoderError::InvalidStride { stride, row_len } =>
+                write!(f, "Stride of {} bytes is shorter than a row of {} bytes", stride, row_len),
+            EncoderError::BufferTooSmall { expected, actual } =>
+                write!(f, "Buffer of {} bytes is too small, {} bytes are needed", actual, expected),
+            EncoderError::BufferSizeMismatch { expected, actual } =>
+                write!(f, "Buffer is {} bytes but the image needs exactly {}", actual, expected),
+            EncoderError::InvalidDimensions { width, height } =>
+                write!(f, "Can't encode a {}x{} image", width, height),
+            EncoderError::InvalidChunk(chunk) =>
+                write!(f, "QOI chunk has a field out of range ({:?})", chunk),
+            EncoderError::ChannelsMismatch { expected, actual } =>
+                write!(f, "Encoder was built for {} channels but asked to encode {}", expected, actual),
+            EncoderError::ColorSpaceMismatch { expected, actual } =>
+                write!(f, "Encoder was built for color space {:?} but asked to encode {:?}", expected, actual),
+
             EncoderError::IoError(e) => fmt::Display::fmt(e, f),
 
             #[allow(unreachable_patterns)]
-            _ => {
-                // NOTE: Remove this if/when this has more thant IoError.
-                let _ = f;
-                unreachable!()
-            }
+            _ => unreachable!()
         }
     }
 }
 
+impl fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscodeError::Decoder(e) => fmt::Display::fmt(e, f),
+            TranscodeError::Encoder(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
 
 impl From<io::Error> for DecoderError {
     fn from(e: io::Error) -> Self {
-        DecoderError::IoError(e)
+        DecoderError::IoError { error: e, position: StreamPosition::default() }
     }
 }
 
@@ -71,11 +236,44 @@ impl From<io::Error> for EncoderError {
     }
 }
 
+impl From<HeaderError> for DecoderError {
+    fn from(e: HeaderError) -> Self {
+        match e {
+            HeaderError::InvalidChannelCount(channels) =>
+                DecoderError::InvalidChannelCount { channels, position: StreamPosition::default() },
+        }
+    }
+}
+
+impl From<HeaderError> for EncoderError {
+    fn from(e: HeaderError) -> Self {
+        match e {
+            HeaderError::InvalidChannelCount(count) => EncoderError::InvalidChannelCount(count),
+        }
+    }
+}
+
+impl From<DecoderError> for TranscodeError {
+    fn from(e: DecoderError) -> Self {
+        TranscodeError::Decoder(e)
+    }
+}
+
+impl From<EncoderError> for TranscodeError {
+    fn from(e: EncoderError) -> Self {
+        TranscodeError::Encoder(e)
+    }
+}
+
 #[cfg(feature = "std")]
 impl From<DecoderError> for std::io::Error {
     fn from(e: DecoderError) -> Self {
         match e {
-            DecoderError::IoError(e) => e,
+            DecoderError::IoError { ref error, .. } => std::io::Error::new(error.kind(), e),
+            DecoderError::Truncated { .. } => std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e),
+            DecoderError::InvalidStride { .. } | DecoderError::BufferTooSmall { .. } | DecoderError::InvalidRegion { .. }
+            | DecoderError::InvalidScale { .. } | DecoderError::DecodeStarted { .. } | DecoderError::FlipUnsupported =>
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
             _ => std::io::Error::new(std::io::ErrorKind::InvalidData, e)
         }
     }
@@ -86,6 +284,11 @@ impl From<EncoderError> for std::io::Error {
     fn from(e: EncoderError) -> Self {
         match e {
             EncoderError::IoError(e) => e,
+            EncoderError::InvalidStride { .. } | EncoderError::BufferTooSmall { .. }
+            | EncoderError::BufferSizeMismatch { .. } | EncoderError::InvalidDimensions { .. }
+            | EncoderError::InvalidChunk(_) | EncoderError::ChannelsMismatch { .. }
+            | EncoderError::ColorSpaceMismatch { .. } =>
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
             #[allow(unreachable_patterns)]
             _ => std::io::Error::new(std::io::ErrorKind::InvalidData, e)
         }
@@ -93,7 +296,30 @@ impl From<EncoderError> for std::io::Error {
 }
 
 #[cfg(feature = "std")]
-impl std::error::Error for DecoderError {}
+impl From<TranscodeError> for std::io::Error {
+    fn from(e: TranscodeError) -> Self {
+        match e {
+            TranscodeError::Decoder(e) => e.into(),
+            TranscodeError::Encoder(e) => e.into()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecoderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecoderError::IoError { error, .. } => Some(error),
+            _ => None
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HeaderError {}
 
 #[cfg(feature = "std")]
 impl std::error::Error for EncoderError {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TranscodeError {}
@@ -0,0 +1,82 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(not(feature = "std"))]
+use crate::io;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{DecoderError, EncoderError, HeaderError, StreamPosition, consts::QoiConsts};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QoiHeader {
+    pub width: u32,
+    pub height: u32,
+    pub channels: u8,
+    /// The raw color space byte, see [`ColorSpace::from_byte`](crate::ColorSpace::from_byte) for its meaning.
+    pub color_space: u8
+}
+
+impl QoiHeader {
+    pub const SIZE: usize = 14;
+
+    pub fn new(width: u32, height: u32, channels: u8, color_space: u8) -> Self {
+        QoiHeader { width, height, channels, color_space }
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Result<Self, DecoderError> {
+        let mut signature = [0; QoiConsts::MAGIC_LEN];
+        signature.copy_from_slice(&bytes[..QoiConsts::MAGIC_LEN]);
+        if signature != QoiConsts::MAGIC {
+            return Err(DecoderError::InvalidSignature { signature, position: StreamPosition::default() });
+        }
+
+        let header = QoiHeader {
+            width: BigEndian::read_u32(&bytes[4..8]),
+            height: BigEndian::read_u32(&bytes[8..12]),
+            channels: bytes[12],
+            color_space: bytes[13]
+        };
+        header.validate()?;
+
+        Ok(header)
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..QoiConsts::MAGIC_LEN].copy_from_slice(&QoiConsts::MAGIC);
+        BigEndian::write_u32(&mut bytes[4..8], self.width);
+        BigEndian::write_u32(&mut bytes[8..12], self.height);
+        bytes[12] = self.channels;
+        bytes[13] = self.color_space;
+        bytes
+    }
+
+    pub fn read_from<R: io::Read>(reader: &mut R) -> Result<Self, DecoderError> {
+        let mut bytes = [0; Self::SIZE];
+        reader.read_exact(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), EncoderError> {
+        self.validate()?;
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), HeaderError> {
+        if !(QoiConsts::CHANNELS_MIN..=QoiConsts::CHANNELS_MAX).contains(&self.channels) {
+            return Err(HeaderError::InvalidChannelCount(self.channels));
+        }
+
+        Ok(())
+    }
+
+    pub fn pixel_count(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// Checks for the QOI magic without parsing the rest of the header.
+pub fn is_qoi(bytes: &[u8]) -> bool {
+    bytes.starts_with(&QoiConsts::MAGIC)
+}
@@ -1,15 +1,17 @@
 
 use std::io;
 
-use image::{ColorType, ImageDecoder, ImageEncoder, ImageError, ImageResult, error::{DecodingError, EncodingError, ImageFormatHint}};
-
-use crate::{ColorSpace, DecoderError, EncoderError, QoiDecoder, QoiEncoder};
-
-impl<R: io::Read> io::Read for QoiDecoder<R> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        Ok(self.decode(buf)?)
+use image::{
+    ColorType, ExtendedColorType, ImageDecoder, ImageDecoderExt, ImageEncoder, ImageError, ImageResult, Progress,
+    error::{
+        DecodingError, EncodingError, ImageFormatHint, ParameterError, ParameterErrorKind, UnsupportedError,
+        UnsupportedErrorKind
     }
-}
+};
+
+use crate::{
+    ColorSpace, DecoderError, EncodeStats, EncoderError, PixelFormat, QoiDecoder, QoiEncoder, encoder::check_buffer
+};
 
 impl<'a, R: 'a + io::Read> ImageDecoder<'a> for QoiDecoder<R> {
     type Reader = Self;
@@ -19,19 +21,70 @@ impl<'a, R: 'a + io::Read> ImageDecoder<'a> for QoiDecoder<R> {
     }
 
     fn color_type(&self) -> ColorType {
-        match self.channels() {
-            3 => ColorType::Rgb8,
-            4 => ColorType::Rgba8,
-            _ => unreachable!()
-        }
+        // Nothing is ever read under this fallback, since `into_reader` refuses those formats.
+        self.image_color_type().unwrap_or(ColorType::Rgba8)
     }
 
     fn into_reader(self) -> ImageResult<Self::Reader> {
+        self.image_color_type()?;
+        self.check_streaming()?;
         Ok(self)
     }
+
+    fn read_image_with_progress<F: Fn(Progress)>(mut self, buf: &mut [u8], _progress_callback: F) -> ImageResult<()> {
+        self.image_color_type()?;
+        let row_len = self.dimensions().0 as usize * self.output_format().bytes_per_pixel() as usize;
+        Ok(self.decode_into_strided(buf, row_len)?)
+    }
 }
 
-impl<'a, W: 'a + io::Write> ImageEncoder for QoiEncoder<'a, W> {
+/// `image` 0.23 has no way to build a [`Progress`] outside the crate, so the callback is never called.
+impl<'a, R: 'a + io::Read> ImageDecoderExt<'a> for QoiDecoder<R> {
+    fn read_rect_with_progress<F: Fn(Progress)>(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        buf: &mut [u8],
+        _progress_callback: F
+    ) -> ImageResult<()> {
+        self.image_color_type()?;
+        Ok(self.decode_region(x, y, width, height, buf)?)
+    }
+}
+
+impl<R: io::Read> QoiDecoder<R> {
+    /// Like [`ImageDecoder::read_image`], but starts each row `stride` bytes after the previous one.
+    pub fn read_image_strided(mut self, buf: &mut [u8], stride: usize) -> ImageResult<()> {
+        self.image_color_type()?;
+        Ok(self.decode_into_strided(buf, stride)?)
+    }
+
+    /// `image` has no color types for ARGB, RGB565 or premultiplied alpha, so those output formats are refused.
+    fn image_color_type(&self) -> ImageResult<ColorType> {
+        match self.output_format() {
+            PixelFormat::Rgb => Ok(ColorType::Rgb8),
+            PixelFormat::Rgba => Ok(ColorType::Rgba8),
+            PixelFormat::Bgr => Ok(ColorType::Bgr8),
+            PixelFormat::Bgra => Ok(ColorType::Bgra8),
+            format => Err(ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                ImageFormatHint::Name("QOI".to_string()),
+                UnsupportedErrorKind::Color(ExtendedColorType::Unknown(format.bytes_per_pixel() * 8))
+            )))
+        }
+    }
+}
+
+// 4x4 Bayer matrix used to dither 16-bit channels down to 8 bits.
+const BAYER: [u32; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+#[inline]
+fn narrow(value: u16, threshold: u32) -> u8 {
+    ((value as u32 * 255 + threshold) / 65535) as u8
+}
+
+impl<W: io::Write> ImageEncoder for QoiEncoder<W> {
     #[inline]
     fn write_image(
         mut self,
@@ -40,25 +93,83 @@ impl<'a, W: 'a + io::Write> ImageEncoder for QoiEncoder<'a, W> {
         height: u32,
         color_type: ColorType,
     ) -> ImageResult<()> {
-        // FIXME: How should I handle ColorSpace here? Currently it just assumes SRGB.
-        match color_type {
-            ColorType::Rgb8 | ColorType::Rgba8 => Ok(QoiEncoder::encode(&mut self, buf, width, height, color_type.bytes_per_pixel(), ColorSpace::Srgb)?),
-            _ => Err(ImageError::IoError(io::Error::new(
+        let color_space = self.options.color_space.unwrap_or(ColorSpace::Srgb);
+        let (format, channels) = match color_type {
+            ColorType::Rgb8 => (PixelFormat::Rgb, 3),
+            ColorType::Rgba8 => (PixelFormat::Rgba, 4),
+            ColorType::Bgr8 => (PixelFormat::Bgr, 3),
+            ColorType::Bgra8 => (PixelFormat::Bgra, 4),
+            ColorType::L8 | ColorType::La8 | ColorType::Rgb16 | ColorType::Rgba16 =>
+                return self.write_converted(buf, width, height, color_type).map(drop).map_err(ImageError::from),
+            _ => return Err(ImageError::IoError(io::Error::new(
                 io::ErrorKind::InvalidInput,
-                format!("Unsupported Color Type: {:?}.  Supported Color Types: RGB(8), RGBA(8).", color_type)
+                format!(
+                    "Unsupported Color Type: {:?}.  Supported Color Types: L(8), LA(8), RGB(8), RGBA(8), BGR(8), BGRA(8), RGB(16), RGBA(16).",
+                    color_type
+                )
             )))
-        }
+        };
+
+        self.options.input = Some(format);
+        self.encode(buf, width, height, self.options.channels.unwrap_or(channels), color_space)?;
+        Ok(())
+    }
+}
+
+impl<W: io::Write> QoiEncoder<W> {
+    /// Expands gray to RGB and narrows 16-bit channels while streaming pixels to the encoder.
+    fn write_converted(mut self, buf: &[u8], width: u32, height: u32, color_type: ColorType) -> Result<EncodeStats, EncoderError> {
+        let bytes_per_pixel = color_type.bytes_per_pixel() as usize;
+        let row_len = width as usize * bytes_per_pixel;
+        let channels = self.options.channels.unwrap_or(if color_type.has_alpha() { 4 } else { 3 });
+        check_buffer(width, height, channels, bytes_per_pixel, buf.len())?;
+
+        let dither = self.options.dither;
+        let pixels = self.options.row_order(height).flat_map(|y| {
+            buf[y * row_len..(y + 1) * row_len].chunks_exact(bytes_per_pixel).enumerate().map(move |(x, pixel)| {
+                let threshold = match dither {
+                    true => (2 * BAYER[(y % 4) * 4 + x % 4] + 1) * 65535 / 32,
+                    false => 32767
+                };
+                let wide = |channel: usize| narrow(u16::from_ne_bytes([pixel[channel * 2], pixel[channel * 2 + 1]]), threshold);
+
+                match color_type {
+                    ColorType::L8 => [pixel[0], pixel[0], pixel[0], 255],
+                    ColorType::La8 => [pixel[0], pixel[0], pixel[0], pixel[1]],
+                    ColorType::Rgb16 => [wide(0), wide(1), wide(2), 255],
+                    _ => [wide(0), wide(1), wide(2), wide(3)]
+                }
+            })
+        });
+
+        let color_space = self.options.color_space.unwrap_or(ColorSpace::Srgb);
+        self.encode_pixels(pixels, width as u64 * height as u64, width, height, channels, color_space)
     }
 }
 
 impl From<DecoderError> for ImageError {
     fn from(e: DecoderError) -> ImageError {
+        if let DecoderError::InvalidStride { .. } | DecoderError::BufferTooSmall { .. } | DecoderError::InvalidRegion { .. } = e {
+            return ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::DimensionMismatch));
+        }
+
+        if let DecoderError::FlipUnsupported = e {
+            return ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                ImageFormatHint::Name("QOI".to_string()),
+                UnsupportedErrorKind::GenericFeature(e.to_string())
+            ));
+        }
+
         ImageError::Decoding(DecodingError::new(ImageFormatHint::Name("QOI".to_string()), e))
     }
 }
 
 impl From<EncoderError> for ImageError {
     fn from(e: EncoderError) -> Self {
+        if let EncoderError::BufferSizeMismatch { .. } | EncoderError::InvalidDimensions { .. } = e {
+            return ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::DimensionMismatch));
+        }
+
         ImageError::Encoding(EncodingError::new(ImageFormatHint::Name("QOI".to_string()), e))
     }
 }
@@ -71,7 +71,7 @@ pub trait Read {
     #[inline]
     fn read_u8(&mut self) -> Result<u8> {
         let mut buf = [0; 1];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(buf[0])
     }
 
@@ -6,18 +6,39 @@ extern crate alloc;
 mod consts;
 mod error;
 mod color_space;
+mod srgb;
+mod header;
+mod limits;
+mod version;
+mod pixel_format;
 mod chunk;
+mod detect;
 mod decoder;
+mod push_decoder;
 mod encoder;
+mod stats;
+mod transcode;
 
 #[cfg(all(feature = "image", feature = "std"))]
 mod image;
 
+#[cfg(feature = "async")]
+mod async_io;
+
 #[cfg(not(feature = "std"))]
 pub mod io;
 
 pub use color_space::ColorSpace;
-use chunk::*;
-pub use error::{DecoderError, EncoderError};
-pub use decoder::QoiDecoder;
-pub use encoder::QoiEncoder;
+pub use version::FormatVersion;
+pub use pixel_format::PixelFormat;
+pub use header::{QoiHeader, is_qoi};
+pub use limits::{DecoderLimits, Limit};
+pub use chunk::{QoiChunk, QoiChunks, ReadQoiChunk, WriteQoiChunk};
+pub use error::{DecoderError, EncoderError, HeaderError, StreamPosition, TranscodeError};
+pub use decoder::{DecodeMode, Pixels, QoiDecoder, QoiDecoderBuilder, Rows};
+pub use push_decoder::QoiPushDecoder;
+pub use encoder::{QoiEncoder, QoiEncoderBuilder};
+pub use stats::{ChunkStats, EncodeStats};
+pub use transcode::transcode;
+#[cfg(feature = "async")]
+pub use async_io::{AsyncQoiDecoder, AsyncQoiEncoder};
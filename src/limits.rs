@@ -0,0 +1,68 @@
+use crate::{DecoderError, QoiHeader, StreamPosition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Width,
+    Height,
+    Pixels,
+    OutputBytes
+}
+
+/// Bounds checked against the header before the decoder is handed out, so callers can size buffers from it safely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecoderLimits {
+    pub max_width: u32,
+    pub max_height: u32,
+    pub max_pixels: u64,
+    pub max_output_bytes: u64
+}
+
+impl DecoderLimits {
+    pub const fn unlimited() -> Self {
+        DecoderLimits {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            max_pixels: u64::MAX,
+            max_output_bytes: u64::MAX
+        }
+    }
+
+    /// Returns the number of pixels in the image, which always fits in a `usize` when this succeeds.
+    pub(crate) fn check(&self, header: &QoiHeader, bytes_per_pixel: u8) -> Result<usize, DecoderError> {
+        fn check(limit: Limit, value: u64, max: u64) -> Result<(), DecoderError> {
+            if value > max {
+                return Err(DecoderError::LimitExceeded { limit, value, max, position: StreamPosition::default() });
+            }
+            Ok(())
+        }
+
+        check(Limit::Width, header.width as u64, self.max_width as u64)?;
+        check(Limit::Height, header.height as u64, self.max_height as u64)?;
+
+        let pixels = header.pixel_count();
+        check(Limit::Pixels, pixels, self.max_pixels)?;
+
+        let max_output_bytes = self.max_output_bytes.min(usize::MAX as u64);
+        match pixels.checked_mul(bytes_per_pixel as u64) {
+            Some(output_bytes) => check(Limit::OutputBytes, output_bytes, max_output_bytes)?,
+            None => return Err(DecoderError::LimitExceeded {
+                limit: Limit::OutputBytes,
+                value: u64::MAX,
+                max: max_output_bytes,
+                position: StreamPosition::default()
+            })
+        }
+
+        Ok(pixels as usize)
+    }
+}
+
+impl Default for DecoderLimits {
+    /// Matches the reference decoder, which refuses images over 400 million pixels.
+    fn default() -> Self {
+        DecoderLimits {
+            max_pixels: 400_000_000,
+            ..Self::unlimited()
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/// Byte layout of the pixels exchanged with the caller, independent of how the image is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PixelFormat {
+    Rgb,
+    Rgba,
+    Bgr,
+    Bgra,
+    Argb,
+    /// 16 bits per pixel, red in the top 5 bits and blue in the bottom 5, stored little-endian.
+    Rgb565,
+    /// RGBA with the color channels multiplied by alpha.
+    RgbaPremultiplied,
+    /// BGRA with the color channels multiplied by alpha.
+    BgraPremultiplied
+}
+
+impl PixelFormat {
+    /// The layout the image itself is stored in.
+    pub fn native(channels: u8) -> Self {
+        match channels {
+            3 => PixelFormat::Rgb,
+            _ => PixelFormat::Rgba
+        }
+    }
+
+    pub const fn bytes_per_pixel(self) -> u8 {
+        match self {
+            PixelFormat::Rgb565 => 2,
+            PixelFormat::Rgb | PixelFormat::Bgr => 3,
+            PixelFormat::Rgba | PixelFormat::Bgra | PixelFormat::Argb |
+            PixelFormat::RgbaPremultiplied | PixelFormat::BgraPremultiplied => 4
+        }
+    }
+
+    /// Writes an RGBA pixel into `out`, which must be exactly [`PixelFormat::bytes_per_pixel`] long.
+    #[inline]
+    pub(crate) fn write(self, pixel: [u8; 4], out: &mut [u8]) {
+        let [r, g, b, a] = pixel;
+        match self {
+            PixelFormat::Rgb => out.copy_from_slice(&[r, g, b]),
+            PixelFormat::Rgba => out.copy_from_slice(&pixel),
+            PixelFormat::Bgr => out.copy_from_slice(&[b, g, r]),
+            PixelFormat::Bgra => out.copy_from_slice(&[b, g, r, a]),
+            PixelFormat::Argb => out.copy_from_slice(&[a, r, g, b]),
+            PixelFormat::Rgb565 => {
+                let packed = ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3);
+                out.copy_from_slice(&packed.to_le_bytes());
+            },
+            PixelFormat::RgbaPremultiplied =>
+                out.copy_from_slice(&[premultiply(r, a), premultiply(g, a), premultiply(b, a), a]),
+            PixelFormat::BgraPremultiplied =>
+                out.copy_from_slice(&[premultiply(b, a), premultiply(g, a), premultiply(r, a), a])
+        }
+    }
+
+    /// Reads an RGBA pixel from `input`, which must be exactly [`PixelFormat::bytes_per_pixel`] long.
+    #[inline]
+    pub(crate) fn read(self, input: &[u8]) -> [u8; 4] {
+        match self {
+            PixelFormat::Rgb => [input[0], input[1], input[2], 255],
+            PixelFormat::Rgba => [input[0], input[1], input[2], input[3]],
+            PixelFormat::Bgr => [input[2], input[1], input[0], 255],
+            PixelFormat::Bgra => [input[2], input[1], input[0], input[3]],
+            PixelFormat::Argb => [input[1], input[2], input[3], input[0]],
+            PixelFormat::Rgb565 => {
+                let packed = u16::from_le_bytes([input[0], input[1]]);
+                let (r, g, b) = ((packed >> 11) as u8, (packed >> 5) as u8 & 0x3f, packed as u8 & 0x1f);
+                [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+            },
+            PixelFormat::RgbaPremultiplied => {
+                let a = input[3];
+                [unpremultiply(input[0], a), unpremultiply(input[1], a), unpremultiply(input[2], a), a]
+            },
+            PixelFormat::BgraPremultiplied => {
+                let a = input[3];
+                [unpremultiply(input[2], a), unpremultiply(input[1], a), unpremultiply(input[0], a), a]
+            }
+        }
+    }
+}
+
+#[inline]
+fn premultiply(channel: u8, alpha: u8) -> u8 {
+    ((channel as u16 * alpha as u16 + 127) / 255) as u8
+}
+
+#[inline]
+fn unpremultiply(channel: u8, alpha: u8) -> u8 {
+    match alpha {
+        0 => 0,
+        _ => core::cmp::min((channel as u16 * 255 + alpha as u16 / 2) / alpha as u16, 255) as u8
+    }
+}
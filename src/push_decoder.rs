@@ -0,0 +1,336 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(not(feature = "std"))]
+use crate::io;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    ColorSpace, DecodeMode, DecoderError, DecoderLimits, FormatVersion, PixelFormat, QoiChunk, QoiDecoderBuilder, QoiHeader,
+    ReadQoiChunk, StreamPosition, consts::QoiConsts, decoder::PixelState, detect::Detector, srgb::ColorConversion
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Header,
+    Probe,
+    Chunks,
+    Trailer,
+    Done
+}
+
+/// A decoder that is fed bytes as they arrive instead of pulling them from a reader.
+///
+/// Chunks split across calls to [`QoiPushDecoder::feed`] are buffered until complete, so it never has to
+/// back out of a partially read chunk the way a blocking reader would.
+pub struct QoiPushDecoder {
+    version: Option<FormatVersion>,
+    limits: DecoderLimits,
+    mode: DecodeMode,
+    output: Option<PixelFormat>,
+    flip_vertical: bool,
+    color_space: Option<ColorSpace>,
+    stage: Stage,
+
+    header_bytes: [u8; QoiHeader::SIZE],
+    header_len: usize,
+    header: Option<QoiHeader>,
+
+    detector: Option<Detector>,
+    probe: Vec<u8>,
+
+    state: PixelState,
+    conversion: ColorConversion,
+    chunk: [u8; QoiConsts::CHUNK_LENGTH_MAX],
+    chunk_len: usize,
+    chunk_pos: usize,
+    offset: u64,
+
+    pixel_count: usize,
+    pixels: usize,
+    row: Vec<u8>,
+    row_len: usize,
+    y: u32,
+
+    trailer: [u8; QoiConsts::END_MARKER_LENGTH],
+    trailer_len: usize
+}
+
+impl QoiDecoderBuilder {
+    /// Builds a [`QoiPushDecoder`] with these options. Without a forced version the first bytes after the
+    /// header are held back until the format has been detected.
+    pub fn build_push(self) -> QoiPushDecoder {
+        QoiPushDecoder {
+            version: self.version,
+            limits: self.limits,
+            mode: self.mode,
+            output: self.output,
+            flip_vertical: self.flip_vertical,
+            color_space: self.color_space,
+            stage: Stage::Header,
+
+            header_bytes: [0; QoiHeader::SIZE],
+            header_len: 0,
+            header: None,
+
+            detector: None,
+            probe: Vec::new(),
+
+            state: PixelState::new(FormatVersion::V1),
+            conversion: ColorConversion::IDENTITY,
+            chunk: [0; QoiConsts::CHUNK_LENGTH_MAX],
+            chunk_len: 0,
+            chunk_pos: 0,
+            offset: QoiHeader::SIZE as u64,
+
+            pixel_count: 0,
+            pixels: 0,
+            row: Vec::new(),
+            row_len: 0,
+            y: 0,
+
+            trailer: [0; QoiConsts::END_MARKER_LENGTH],
+            trailer_len: 0
+        }
+    }
+}
+
+impl Default for QoiPushDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QoiPushDecoder {
+    pub fn new() -> Self {
+        QoiDecoderBuilder::new().build_push()
+    }
+
+    pub fn header(&self) -> Option<&QoiHeader> {
+        self.header.as_ref()
+    }
+
+    /// The version being decoded, once it has been forced or detected.
+    pub fn format_version(&self) -> Option<FormatVersion> {
+        self.version
+    }
+
+    pub fn rows_decoded(&self) -> u32 {
+        self.y
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stage == Stage::Done
+    }
+
+    /// Consumes as much of `data` as belongs to the image, calling `on_row` with each completed row.
+    ///
+    /// Returns the number of bytes consumed, which is less than `data.len()` only once the image is finished.
+    pub fn feed<F: FnMut(u32, &[u8])>(&mut self, data: &[u8], mut on_row: F) -> Result<usize, DecoderError> {
+        let mut consumed = 0;
+        while consumed < data.len() {
+            match self.stage {
+                Stage::Header => {
+                    let amt = core::cmp::min(data.len() - consumed, QoiHeader::SIZE - self.header_len);
+                    self.header_bytes[self.header_len..self.header_len + amt].copy_from_slice(&data[consumed..consumed + amt]);
+                    self.header_len += amt;
+                    consumed += amt;
+
+                    if self.header_len == QoiHeader::SIZE {
+                        self.read_header()?;
+                    }
+                },
+                Stage::Probe => {
+                    let byte = data[consumed];
+                    consumed += 1;
+
+                    let detector = self.detector.as_mut().unwrap();
+                    self.probe.push(byte);
+                    detector.push(byte);
+
+                    let version = match detector.version() {
+                        Some(version) => version,
+                        None if self.probe.len() == QoiConsts::PROBE_LENGTH => detector.guess(),
+                        None => continue
+                    };
+                    self.start(version, &mut on_row)?;
+                },
+                Stage::Chunks | Stage::Trailer => {
+                    self.push_byte(data[consumed], &mut on_row)?;
+                    consumed += 1;
+                },
+                Stage::Done => {
+                    if self.mode == DecodeMode::Strict {
+                        return Err(DecoderError::TrailingData { position: self.position() });
+                    }
+                    break;
+                }
+            }
+        }
+
+        Ok(consumed)
+    }
+
+    /// Signals the end of the input, flushing anything held back for detection.
+    pub fn finish<F: FnMut(u32, &[u8])>(&mut self, mut on_row: F) -> Result<(), DecoderError> {
+        if self.stage == Stage::Probe {
+            let version = self.detector.as_ref().unwrap().guess();
+            self.start(version, &mut on_row)?;
+        }
+
+        match self.stage {
+            Stage::Done => Ok(()),
+            Stage::Trailer if self.mode == DecodeMode::Lenient => {
+                log::warn!("QOI stream is missing its padding");
+                self.stage = Stage::Done;
+                Ok(())
+            },
+            Stage::Chunks if self.mode == DecodeMode::Lenient => {
+                log::warn!("QOI stream ended after {} of {} pixels", self.pixels, self.pixel_count);
+                let remaining = self.pixel_count - self.pixels;
+                self.emit(remaining, &mut on_row);
+                self.stage = Stage::Done;
+                Ok(())
+            },
+            Stage::Chunks | Stage::Trailer => Err(DecoderError::Truncated {
+                expected_pixels: self.pixel_count as u64,
+                decoded_pixels: self.pixels as u64,
+                position: self.position()
+            }),
+            _ => {
+                #[cfg(feature = "std")]
+                let error = io::Error::from(io::ErrorKind::UnexpectedEof);
+                #[cfg(not(feature = "std"))]
+                let error = io::Error::UnexpectedEof;
+                let position = StreamPosition { offset: self.header_len as u64, ..StreamPosition::default() };
+                Err(DecoderError::IoError { error, position })
+            }
+        }
+    }
+
+    fn position(&self) -> StreamPosition {
+        StreamPosition::new(self.offset, self.pixels as u64, self.header.map_or(0, |header| header.width))
+    }
+
+    fn read_header(&mut self) -> Result<(), DecoderError> {
+        let header = QoiHeader::from_bytes(&self.header_bytes)?;
+        let output = *self.output.get_or_insert(PixelFormat::native(header.channels));
+        self.pixel_count = self.limits.check(&header, output.bytes_per_pixel())?;
+        self.row = vec![0; header.width as usize * output.bytes_per_pixel() as usize];
+        self.header = Some(header);
+
+        match self.version {
+            Some(version) => self.start(version, &mut |_, _| {}),
+            None => {
+                let detector = Detector::new(header.pixel_count(), header.color_space);
+                match detector.version() {
+                    Some(version) => self.start(version, &mut |_, _| {}),
+                    None => {
+                        self.detector = Some(detector);
+                        self.stage = Stage::Probe;
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, version: FormatVersion, on_row: &mut dyn FnMut(u32, &[u8])) -> Result<(), DecoderError> {
+        self.mode.check_color_space(&self.header.unwrap(), version)?;
+
+        self.version = Some(version);
+        self.detector = None;
+        self.state = PixelState::new(version);
+        let color_space = ColorSpace::from_byte(self.header.unwrap().color_space, version);
+        self.conversion = ColorConversion::resolve(color_space, self.color_space).1;
+        self.stage = if self.pixel_count == 0 { Stage::Trailer } else { Stage::Chunks };
+
+        for byte in core::mem::take(&mut self.probe) {
+            if self.stage == Stage::Done {
+                if self.mode == DecodeMode::Strict {
+                    return Err(DecoderError::TrailingData { position: self.position() });
+                }
+                break;
+            }
+            self.push_byte(byte, on_row)?;
+        }
+
+        Ok(())
+    }
+
+    fn push_byte(&mut self, byte: u8, on_row: &mut dyn FnMut(u32, &[u8])) -> Result<(), DecoderError> {
+        if self.stage == Stage::Trailer {
+            return self.push_trailer(byte);
+        }
+
+        let version = self.state.version;
+        if self.chunk_pos == 0 {
+            self.chunk_len = QoiChunk::len_from_first_byte(byte, version);
+        }
+        self.chunk[self.chunk_pos] = byte;
+        self.chunk_pos += 1;
+        if self.chunk_pos < self.chunk_len {
+            return Ok(());
+        }
+        self.chunk_pos = 0;
+
+        let chunk = (&self.chunk[..self.chunk_len]).read_qoi_chunk(version)
+            .map_err(|e| e.at(self.position(), self.pixel_count as u64))?;
+        self.state.apply(chunk);
+
+        let count = 1 + core::mem::take(&mut self.state.run);
+        let remaining = self.pixel_count - self.pixels;
+        if count > remaining && self.mode == DecodeMode::Strict {
+            return Err(DecoderError::RunOverflow { overflow: count - remaining, position: self.position() });
+        }
+        self.offset += self.chunk_len as u64;
+        self.emit(core::cmp::min(count, remaining), on_row);
+
+        if self.pixels == self.pixel_count {
+            self.stage = Stage::Trailer;
+        }
+
+        Ok(())
+    }
+
+    fn push_trailer(&mut self, byte: u8) -> Result<(), DecoderError> {
+        let version = self.state.version;
+
+        self.trailer[self.trailer_len] = byte;
+        self.trailer_len += 1;
+        if self.trailer_len < version.trailer().len() {
+            return Ok(());
+        }
+        self.stage = Stage::Done;
+
+        self.mode.check_trailer(version, &self.trailer[..self.trailer_len])
+            .map_err(|e| e.at(self.position(), self.pixel_count as u64))?;
+        self.offset += self.trailer_len as u64;
+        Ok(())
+    }
+
+    fn emit(&mut self, count: usize, on_row: &mut dyn FnMut(u32, &[u8])) {
+        let output = self.output.unwrap();
+        let bytes_per_pixel = output.bytes_per_pixel() as usize;
+
+        let mut pixel = self.state.pixel;
+        if self.header.unwrap().channels == 3 {
+            pixel[3] = 255;
+        }
+        let pixel = self.conversion.apply(pixel);
+
+        for _ in 0..count {
+            output.write(pixel, &mut self.row[self.row_len..self.row_len + bytes_per_pixel]);
+            self.row_len += bytes_per_pixel;
+            self.pixels += 1;
+
+            if self.row_len == self.row.len() {
+                let header = self.header.unwrap();
+                on_row(if self.flip_vertical { header.height - 1 - self.y } else { self.y }, &self.row);
+                self.y += 1;
+                self.row_len = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::QoiChunk;
+
+/// How many chunks of one type were written, and how many bytes they took up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkStats {
+    pub count: u64,
+    pub bytes: u64
+}
+
+/// A breakdown of an encoded image by chunk type, returned from every encode.
+#[derive(Debug, Clone, Default)]
+pub struct EncodeStats {
+    pub pixels: u64,
+    /// Size of the pixels as stored, `pixels * channels`.
+    pub raw_bytes: u64,
+    /// Size of the encoded image, including the header and padding.
+    pub encoded_bytes: u64,
+    /// Most consecutive pixels covered by run chunks.
+    pub longest_run: u64,
+
+    pub index: ChunkStats,
+    pub run8: ChunkStats,
+    pub run16: ChunkStats,
+    pub diff8: ChunkStats,
+    pub diff16: ChunkStats,
+    pub diff24: ChunkStats,
+    /// Draft color chunks indexed by their channel mask, from red in bit 3 down to alpha in bit 0.
+    pub color: [ChunkStats; 16],
+
+    pub diff: ChunkStats,
+    pub luma: ChunkStats,
+    pub run: ChunkStats,
+    pub rgb: ChunkStats,
+    pub rgba: ChunkStats,
+
+    current_run: u64
+}
+
+// Only the public counters are compared, since `current_run` depends on where the last run ended.
+impl PartialEq for EncodeStats {
+    fn eq(&self, other: &Self) -> bool {
+        (self.pixels, self.raw_bytes, self.encoded_bytes, self.longest_run)
+            == (other.pixels, other.raw_bytes, other.encoded_bytes, other.longest_run)
+            && self.all().eq(other.all())
+    }
+}
+
+impl Eq for EncodeStats {}
+
+impl EncodeStats {
+    fn all(&self) -> impl Iterator<Item = &ChunkStats> {
+        [&self.index, &self.run8, &self.run16, &self.diff8, &self.diff16, &self.diff24]
+            .into_iter()
+            .chain(self.color.iter())
+            .chain([&self.diff, &self.luma, &self.run, &self.rgb, &self.rgba])
+    }
+
+    /// Total number of chunks written.
+    pub fn chunks(&self) -> u64 {
+        self.all().map(|stats| stats.count).sum()
+    }
+
+    /// Share of the pixels outside of runs that were found in the index.
+    pub fn index_hit_rate(&self) -> f64 {
+        let lookups = self.chunks() - self.run8.count - self.run16.count - self.run.count;
+        match lookups {
+            0 => 0.0,
+            _ => self.index.count as f64 / lookups as f64
+        }
+    }
+
+    /// Encoded size over raw size, so smaller is better.
+    pub fn compression_ratio(&self) -> f64 {
+        match self.raw_bytes {
+            0 => 0.0,
+            _ => self.encoded_bytes as f64 / self.raw_bytes as f64
+        }
+    }
+
+    pub(crate) fn record(&mut self, chunk: &QoiChunk, bytes: usize) {
+        let stats = match *chunk {
+            QoiChunk::Index(_) => &mut self.index,
+            QoiChunk::Run8(_) => &mut self.run8,
+            QoiChunk::Run16(_) => &mut self.run16,
+            QoiChunk::Diff8(..) => &mut self.diff8,
+            QoiChunk::Diff16(..) => &mut self.diff16,
+            QoiChunk::Diff24(..) => &mut self.diff24,
+            QoiChunk::Color(r, g, b, a) => {
+                let mask = (r.is_some() as usize) << 3 | (g.is_some() as usize) << 2
+                    | (b.is_some() as usize) << 1 | a.is_some() as usize;
+                &mut self.color[mask]
+            },
+            QoiChunk::Diff(..) => &mut self.diff,
+            QoiChunk::Luma(..) => &mut self.luma,
+            QoiChunk::Run(_) => &mut self.run,
+            QoiChunk::Rgb(..) => &mut self.rgb,
+            QoiChunk::Rgba(..) => &mut self.rgba
+        };
+        stats.count += 1;
+        stats.bytes += bytes as u64;
+
+        self.current_run = match chunk {
+            QoiChunk::Run8(_) | QoiChunk::Run16(_) | QoiChunk::Run(_) => self.current_run + chunk.pixel_count() as u64,
+            _ => 0
+        };
+        self.longest_run = self.longest_run.max(self.current_run);
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(not(feature = "std"))]
+use crate::io;
+
+use crate::{FormatVersion, QoiDecoder, QoiEncoder, TranscodeError, encoder::PixelEncoder};
+
+/// Re-encodes the image behind `decoder` as `version`, streaming one pixel at a time.
+pub fn transcode<R: io::Read, W: io::Write>(
+    decoder: &mut QoiDecoder<R>,
+    writer: &mut W,
+    version: FormatVersion
+) -> Result<(), TranscodeError> {
+    let (width, height) = decoder.dimensions();
+
+    let mut encoder = QoiEncoder::new_with_version(writer, version);
+    encoder.write_header(width, height, decoder.channels(), decoder.color_space())?;
+
+    let mut pixel_encoder = PixelEncoder::new(encoder.version(), width as u64 * height as u64);
+    for pixel in decoder.pixels() {
+        pixel_encoder.push(encoder.get_mut(), pixel?)?;
+    }
+
+    encoder.write_trailer()?;
+
+    Ok(())
+}
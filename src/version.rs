@@ -0,0 +1,17 @@
+use crate::consts::QoiConsts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatVersion {
+    Draft,
+    V1
+}
+
+impl FormatVersion {
+    /// The bytes every stream of this version ends with.
+    pub(crate) fn trailer(self) -> &'static [u8] {
+        match self {
+            FormatVersion::Draft => &QoiConsts::PADDING,
+            FormatVersion::V1 => &QoiConsts::END_MARKER
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use qoi::{self, ColorSpace, EncoderError, FormatVersion, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+const EXPECTED: &[u8] = include_bytes!("./image_v1.qoi");
+
+#[test]
+fn raw_to_qoi_v1() -> Result<(), EncoderError> {
+    env_logger::init();
+
+    let mut encoded = vec![];
+    QoiEncoder::new_with_version(&mut encoded, FormatVersion::V1)
+        .encode(INITIAL, 382, 480, 4, ColorSpace::SrgbLinearAlpha)?;
+
+    compare_bytes(&encoded, EXPECTED);
+
+    Ok(())
+}
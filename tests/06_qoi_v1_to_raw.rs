@@ -0,0 +1,25 @@
+use qoi::{self, ColorSpace, DecoderError, FormatVersion, QoiDecoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+#[test]
+fn qoi_v1_to_raw() -> Result<(), DecoderError> {
+    env_logger::init();
+
+    let mut decoder = QoiDecoder::new_with_version(INITIAL, FormatVersion::V1)?;
+    assert_eq!(decoder.format_version(), FormatVersion::V1);
+    assert_eq!(decoder.color_space(), ColorSpace::SrgbLinearAlpha);
+
+    let (width, height) = decoder.dimensions();
+    let channels = decoder.channels();
+    let mut decoded = vec![0u8; width as usize * height as usize * channels as usize];
+    decoder.decode(&mut decoded)?;
+
+    compare_bytes(&decoded, EXPECTED);
+
+    Ok(())
+}
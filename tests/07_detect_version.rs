@@ -0,0 +1,68 @@
+use qoi::{self, ColorSpace, DecoderError, FormatVersion, QoiDecoder, QoiEncoder};
+
+const DRAFT: &[u8] = include_bytes!("./image.qoi");
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+
+fn encode(buf: &[u8], width: u32, height: u32, channels: u8, version: FormatVersion) -> Vec<u8> {
+    let mut encoded = vec![];
+    QoiEncoder::new_with_version(&mut encoded, version)
+        .encode(buf, width, height, channels, ColorSpace::Srgb)
+        .unwrap();
+    encoded
+}
+
+#[test]
+fn detect_version() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    assert_eq!(QoiDecoder::new(DRAFT)?.format_version(), FormatVersion::Draft);
+    assert_eq!(QoiDecoder::new(V1)?.format_version(), FormatVersion::V1);
+
+    Ok(())
+}
+
+#[test]
+fn detect_small_images() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut seed = 0x2545f491u32;
+    let noise: Vec<u8> = (0..16 * 16 * 4).map(|_| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as u8
+    }).collect();
+    let gradient: Vec<u8> = (0..7 * 5 * 3).map(|i| (i * 3) as u8).collect();
+    let solid = [0x20, 0x40, 0x60, 0xff];
+
+    let images: [(&[u8], u32, u32, u8); 3] = [
+        (&noise, 16, 16, 4),
+        (&gradient, 7, 5, 3),
+        (&solid, 1, 1, 4),
+    ];
+
+    for (buf, width, height, channels) in images {
+        for version in [FormatVersion::Draft, FormatVersion::V1] {
+            let encoded = encode(buf, width, height, channels, version);
+
+            let mut decoder = QoiDecoder::new(&encoded[..])?;
+            assert_eq!(decoder.format_version(), version, "{}x{}x{}", width, height, channels);
+
+            let mut decoded = vec![0; buf.len()];
+            decoder.decode(&mut decoded)?;
+            assert_eq!(decoded, buf);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn force_version() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let decoder = QoiDecoder::new_with_version(DRAFT, FormatVersion::V1)?;
+    assert_eq!(decoder.format_version(), FormatVersion::V1);
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use qoi::{self, FormatVersion, QoiDecoder, TranscodeError};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image_v1.qoi");
+
+#[test]
+fn draft_to_v1() -> Result<(), TranscodeError> {
+    env_logger::init();
+
+    let mut decoder = QoiDecoder::new_with_version(INITIAL, FormatVersion::Draft)?;
+    let mut transcoded = vec![];
+    qoi::transcode(&mut decoder, &mut transcoded, FormatVersion::V1)?;
+
+    compare_bytes(&transcoded, EXPECTED);
+
+    Ok(())
+}
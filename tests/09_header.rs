@@ -0,0 +1,34 @@
+use qoi::{self, ColorSpace, DecoderError, EncoderError, HeaderError, QoiEncoder, QoiHeader};
+
+const INITIAL: &[u8] = include_bytes!("./image.qoi");
+
+#[test]
+fn read_header() -> Result<(), DecoderError> {
+    assert!(qoi::is_qoi(INITIAL));
+    assert!(!qoi::is_qoi(b"\x89PNG"));
+
+    let header = QoiHeader::read_from(&mut &INITIAL[..])?;
+    assert_eq!(header, QoiHeader::new(382, 480, 4, 0));
+    assert_eq!(header.to_bytes()[..], INITIAL[..QoiHeader::SIZE]);
+
+    let mut written = vec![];
+    header.write_to(&mut written).unwrap();
+    assert_eq!(QoiHeader::from_bytes(&written[..].try_into().unwrap())?, header);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_header() {
+    let mut bytes = QoiHeader::new(1, 1, 2, 0).to_bytes();
+    assert_eq!(QoiHeader::new(1, 1, 2, 0).validate(), Err(HeaderError::InvalidChannelCount(2)));
+    assert!(matches!(QoiHeader::from_bytes(&bytes), Err(DecoderError::InvalidChannelCount { channels: 2, .. })));
+
+    bytes[0] = b'Q';
+    assert!(matches!(QoiHeader::from_bytes(&bytes), Err(DecoderError::InvalidSignature { .. })));
+
+    let mut encoded = vec![];
+    let result = QoiEncoder::new(&mut encoded).encode(&[0; 2], 1, 1, 2, ColorSpace::Srgb);
+    assert!(matches!(result, Err(EncoderError::InvalidChannelCount(2))));
+    assert!(encoded.is_empty());
+}
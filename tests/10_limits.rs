@@ -0,0 +1,28 @@
+use qoi::{self, DecoderError, DecoderLimits, FormatVersion, Limit, QoiDecoder, QoiHeader};
+
+const INITIAL: &[u8] = include_bytes!("./image.qoi");
+
+#[test]
+fn header_limits() -> Result<(), DecoderError> {
+    let bomb = QoiHeader::new(u32::MAX, u32::MAX, 4, 0).to_bytes();
+    assert!(matches!(
+        QoiDecoder::new(&bomb[..]),
+        Err(DecoderError::LimitExceeded { limit: Limit::Pixels, .. })
+    ));
+    assert!(matches!(
+        QoiDecoder::builder().limits(DecoderLimits::unlimited()).build(&bomb[..]),
+        Err(DecoderError::LimitExceeded { limit: Limit::OutputBytes, .. })
+    ));
+
+    let limits = DecoderLimits { max_width: 256, ..DecoderLimits::default() };
+    assert!(matches!(
+        QoiDecoder::builder().limits(limits).build(INITIAL),
+        Err(DecoderError::LimitExceeded { limit: Limit::Width, value: 382, max: 256, .. })
+    ));
+
+    let limits = DecoderLimits { max_output_bytes: 382 * 480 * 4, ..DecoderLimits::default() };
+    let decoder = QoiDecoder::builder().limits(limits).version(FormatVersion::Draft).build(INITIAL)?;
+    assert_eq!(decoder.dimensions(), (382, 480));
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use qoi::{self, DecodeMode, DecoderError, FormatVersion, QoiDecoder, QoiHeader};
+
+const INITIAL: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+fn decode(data: &[u8], mode: DecodeMode) -> Result<Vec<u8>, DecoderError> {
+    let mut decoder = QoiDecoder::builder()
+        .version(FormatVersion::V1)
+        .mode(mode)
+        .build(data)?;
+    let (width, height) = decoder.dimensions();
+    let mut decoded = vec![0; width as usize * height as usize * decoder.channels() as usize];
+    decoder.decode(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[test]
+fn strict() {
+    let _ = env_logger::try_init();
+
+    assert_eq!(decode(INITIAL, DecodeMode::Strict).unwrap(), EXPECTED);
+
+    let mut trailing = INITIAL.to_vec();
+    trailing.push(0);
+    assert!(decode(&trailing, DecodeMode::Normal).is_ok());
+    assert!(matches!(decode(&trailing, DecodeMode::Strict), Err(DecoderError::TrailingData { .. })));
+
+    let mut color_space = INITIAL.to_vec();
+    color_space[13] = 0x20;
+    assert!(decode(&color_space, DecodeMode::Normal).is_ok());
+    assert!(matches!(decode(&color_space, DecodeMode::Strict), Err(DecoderError::InvalidColorSpace { color_space: 0x20, .. })));
+
+    // A run of five pixels in a two pixel image.
+    let mut overflow = QoiHeader::new(2, 1, 4, 0).to_bytes().to_vec();
+    overflow.extend_from_slice(&[0xc4, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(decode(&overflow, DecodeMode::Normal).unwrap(), [0, 0, 0, 255, 0, 0, 0, 255]);
+    assert!(matches!(decode(&overflow, DecodeMode::Strict), Err(DecoderError::RunOverflow { overflow: 3, .. })));
+}
+
+#[test]
+fn lenient() {
+    let _ = env_logger::try_init();
+
+    let mut garbled = INITIAL.to_vec();
+    *garbled.last_mut().unwrap() = 0xff;
+    assert!(matches!(decode(&garbled, DecodeMode::Normal), Err(DecoderError::InvalidEndMarker { .. })));
+    assert_eq!(decode(&garbled, DecodeMode::Lenient).unwrap(), EXPECTED);
+
+    let missing = &INITIAL[..INITIAL.len() - 8];
+    assert!(decode(missing, DecodeMode::Normal).is_err());
+    assert_eq!(decode(missing, DecodeMode::Lenient).unwrap(), EXPECTED);
+
+    let truncated = &INITIAL[..INITIAL.len() / 2];
+    assert!(decode(truncated, DecodeMode::Normal).is_err());
+    let decoded = decode(truncated, DecodeMode::Lenient).unwrap();
+    let decoded_pixels = decoded.chunks_exact(4).zip(EXPECTED.chunks_exact(4)).take_while(|(a, b)| a == b).count();
+    assert!(decoded_pixels > 0);
+    let last = &decoded[(decoded_pixels - 1) * 4..decoded_pixels * 4];
+    assert!(decoded[decoded_pixels * 4..].chunks_exact(4).all(|pixel| pixel == last));
+}
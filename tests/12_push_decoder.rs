@@ -0,0 +1,62 @@
+use qoi::{self, DecodeMode, DecoderError, FormatVersion, QoiDecoder, QoiPushDecoder};
+
+mod common;
+use common::compare_bytes;
+
+const DRAFT: &[u8] = include_bytes!("./image.qoi");
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+fn feed_in(decoder: &mut QoiPushDecoder, data: &[u8], size: usize) -> Result<Vec<u8>, DecoderError> {
+    let mut decoded = vec![];
+    let mut next_row = 0;
+    for part in data.chunks(size) {
+        let consumed = decoder.feed(part, |y, row| {
+            assert_eq!(y, next_row);
+            next_row += 1;
+            decoded.extend_from_slice(row);
+        })?;
+        assert_eq!(consumed, part.len());
+    }
+    decoder.finish(|_, row| decoded.extend_from_slice(row))?;
+    Ok(decoded)
+}
+
+#[test]
+fn split_feeds() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    for (data, version) in [(DRAFT, FormatVersion::Draft), (V1, FormatVersion::V1)] {
+        for size in [1, 2, 3, 5, 7, 4096] {
+            let mut decoder = QoiPushDecoder::new();
+            compare_bytes(&feed_in(&mut decoder, data, size)?, EXPECTED);
+            assert_eq!(decoder.format_version(), Some(version));
+            assert_eq!(decoder.rows_decoded(), 480);
+            assert!(decoder.is_finished());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn trailing_and_truncated() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut data = V1.to_vec();
+    data.extend_from_slice(b"qoif");
+    let mut decoder = QoiDecoder::builder().version(FormatVersion::V1).build_push();
+    assert_eq!(decoder.feed(&data, |_, _| {})?, V1.len());
+    assert!(decoder.is_finished());
+
+    let truncated = &V1[..V1.len() / 2];
+    let mut decoder = QoiPushDecoder::new();
+    decoder.feed(truncated, |_, _| {})?;
+    assert!(decoder.finish(|_, _| {}).is_err());
+
+    let mut decoder = QoiDecoder::builder().mode(DecodeMode::Lenient).build_push();
+    let decoded = feed_in(&mut decoder, truncated, 4096)?;
+    assert_eq!(decoded.len(), EXPECTED.len());
+
+    Ok(())
+}
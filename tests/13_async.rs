@@ -0,0 +1,134 @@
+#![cfg(feature = "async")]
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use qoi::{
+    self, AsyncQoiDecoder, AsyncQoiEncoder, ColorSpace, DecoderError, EncoderError, FormatVersion, PixelFormat, QoiDecoder,
+    QoiEncoder
+};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+const EXPECTED: &[u8] = include_bytes!("./image_v1.qoi");
+
+#[tokio::test]
+async fn encode_over_duplex() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let _ = env_logger::try_init();
+
+    let (writer, mut reader) = tokio::io::duplex(64);
+    let mut encoder = AsyncQoiEncoder::new_with_version(writer, FormatVersion::V1);
+
+    let mut encoded = vec![];
+    let (encode, read) = tokio::join!(
+        async {
+            encoder.encode(INITIAL, 382, 480, 4, ColorSpace::SrgbLinearAlpha).await?;
+            drop(encoder);
+            Ok::<_, qoi::EncoderError>(())
+        },
+        reader.read_to_end(&mut encoded)
+    );
+    encode?;
+    read?;
+
+    compare_bytes(&encoded, EXPECTED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn encoder_options_match_blocking() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let builder = QoiEncoder::builder()
+        .version(FormatVersion::V1)
+        .input_format(PixelFormat::Bgra)
+        .input_color_space(ColorSpace::SrgbLinearAlpha)
+        .flip_vertical(true);
+
+    let mut expected = vec![];
+    let expected_stats = builder.build(&mut expected).encode(INITIAL, 382, 480, 4, ColorSpace::Srgb)?;
+
+    let mut encoded = vec![];
+    let stats = builder.build_async(&mut encoded).encode(INITIAL, 382, 480, 4, ColorSpace::Srgb).await?;
+    compare_bytes(&encoded, &expected);
+    assert_eq!(stats, expected_stats);
+
+    let mut nothing = vec![];
+    let dry_stats = builder.dry_run(true).build_async(&mut nothing).encode(INITIAL, 382, 480, 4, ColorSpace::Srgb).await?;
+    assert!(nothing.is_empty());
+    assert_eq!(dry_stats, expected_stats);
+
+    assert!(matches!(
+        builder.channels(3).build_async(&mut nothing).encode(INITIAL, 382, 480, 4, ColorSpace::Srgb).await,
+        Err(EncoderError::ChannelsMismatch { expected: 3, actual: 4 })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn decode_over_duplex() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let _ = env_logger::try_init();
+
+    let (mut writer, reader) = tokio::io::duplex(64);
+
+    let (write, decoded) = tokio::join!(
+        async {
+            tokio::io::AsyncWriteExt::write_all(&mut writer, EXPECTED).await?;
+            drop(writer);
+            Ok::<_, std::io::Error>(())
+        },
+        async {
+            let mut decoder = AsyncQoiDecoder::new(reader).await?;
+            assert_eq!(decoder.format_version(), FormatVersion::V1);
+            assert_eq!(decoder.dimensions(), (382, 480));
+
+            let mut decoded = vec![0; INITIAL.len()];
+            let mut read = 0;
+            while read < decoded.len() {
+                read += decoder.decode(&mut decoded[read..read + 1000.min(INITIAL.len() - read)]).await?;
+            }
+            assert_eq!(decoder.decode(&mut [0; 4]).await?, 0);
+            Ok::<_, qoi::DecoderError>(decoded)
+        }
+    );
+    write?;
+
+    compare_bytes(&decoded?, INITIAL);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn decode_from_open_stream() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let _ = env_logger::try_init();
+
+    let pixels = [10, 20, 30, 255, 40, 50, 60, 128];
+    for version in [FormatVersion::Draft, FormatVersion::V1] {
+        let mut encoded = vec![];
+        QoiEncoder::new_with_version(&mut encoded, version).encode(&pixels, 2, 1, 4, ColorSpace::Srgb)?;
+
+        for builder in [QoiDecoder::builder(), QoiDecoder::builder().version(version)] {
+            // The writer stays open, so nothing past the image ever arrives.
+            let (mut writer, reader) = tokio::io::duplex(64);
+            writer.write_all(&encoded).await?;
+
+            let decode = async {
+                let mut decoder = builder.build_async(reader).await?;
+                assert_eq!(decoder.format_version(), version);
+
+                let mut decoded = [0; 8];
+                assert_eq!(decoder.decode(&mut decoded).await?, decoded.len());
+                Ok::<_, DecoderError>(decoded)
+            };
+            assert_eq!(tokio::time::timeout(Duration::from_secs(5), decode).await??, pixels);
+            drop(writer);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use qoi::{self, ColorSpace, DecodeMode, DecoderError, FormatVersion, QoiDecoder, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const DRAFT: &[u8] = include_bytes!("./image.qoi");
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+#[test]
+fn rows() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    for data in [DRAFT, V1] {
+        let mut decoder = QoiDecoder::new(data)?;
+        let mut rows = decoder.rows();
+
+        let mut decoded = vec![];
+        let mut count = 0;
+        while let Some(row) = rows.next() {
+            let row = row?;
+            assert_eq!(row.len(), 382 * 4);
+            decoded.extend_from_slice(row);
+            count += 1;
+        }
+
+        assert_eq!(count, 480);
+        compare_bytes(&decoded, EXPECTED);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pixels() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::new(V1)?;
+    let decoded = decoder.pixels().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(decoded.len(), 382 * 480);
+    compare_bytes(&decoded.concat(), EXPECTED);
+
+    let rgb = [10, 20, 30, 10, 20, 30, 40, 50, 60];
+    let mut encoded = vec![];
+    QoiEncoder::new_with_version(&mut encoded, FormatVersion::V1).encode(&rgb, 3, 1, 3, ColorSpace::Srgb).unwrap();
+    let mut decoder = QoiDecoder::new(&encoded[..])?;
+    let decoded = decoder.pixels().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(decoded, vec![[10, 20, 30, 255], [10, 20, 30, 255], [40, 50, 60, 255]]);
+
+    Ok(())
+}
+
+#[test]
+fn iterators_report_errors_once() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut data = V1.to_vec();
+    let len = data.len();
+    data[len - 1] = 0;
+
+    let mut decoder = QoiDecoder::new(&data[..])?;
+    let mut rows = decoder.rows();
+    let mut errors = 0;
+    while let Some(row) = rows.next() {
+        errors += row.is_err() as usize;
+    }
+    assert_eq!(errors, 1);
+
+    let mut decoder = QoiDecoder::new(&data[..])?;
+    let results = decoder.pixels().collect::<Vec<_>>();
+    assert_eq!(results.len(), 382 * 480 + 1);
+    assert!(matches!(results.last(), Some(Err(DecoderError::InvalidEndMarker { .. }))));
+
+    let mut decoder = QoiDecoder::builder().mode(DecodeMode::Strict).build(&V1[..V1.len() / 2])?;
+    let results = decoder.pixels().collect::<Vec<_>>();
+    assert!(results.last().unwrap().is_err());
+    assert_eq!(results.iter().filter(|pixel| pixel.is_err()).count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn rows_before_bad_end_marker() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut data = V1.to_vec();
+    let len = data.len();
+    data[len - 1] = 0;
+
+    let mut decoder = QoiDecoder::new(&data[..])?;
+    let mut rows = decoder.rows();
+
+    let mut decoded = vec![];
+    for _ in 0..480 {
+        decoded.extend_from_slice(rows.next().unwrap()?);
+    }
+    compare_bytes(&decoded, EXPECTED);
+    assert!(matches!(rows.next(), Some(Err(DecoderError::InvalidEndMarker { .. }))));
+    assert!(rows.next().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use qoi::{self, DecoderError, QoiDecoder};
+
+mod common;
+use common::compare_bytes;
+
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+const ROW_LEN: usize = 382 * 4;
+
+fn check_strided(buf: &[u8], stride: usize) {
+    for (y, row) in buf.chunks(stride).enumerate() {
+        compare_bytes(&row[..ROW_LEN], &EXPECTED[y * ROW_LEN..(y + 1) * ROW_LEN]);
+        assert!(row[ROW_LEN..].iter().all(|&byte| byte == 0xaa));
+    }
+}
+
+#[test]
+fn decode_into_strided() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let stride = ROW_LEN + 64;
+    let mut buf = vec![0xaa; stride * 479 + ROW_LEN];
+    QoiDecoder::new(V1)?.decode_into_strided(&mut buf, stride)?;
+    check_strided(&buf, stride);
+
+    let mut packed = vec![0; EXPECTED.len()];
+    QoiDecoder::new(V1)?.decode_into_strided(&mut packed, ROW_LEN)?;
+    compare_bytes(&packed, EXPECTED);
+
+    Ok(())
+}
+
+#[test]
+fn rejects_bad_buffers() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut buf = vec![0; EXPECTED.len()];
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_into_strided(&mut buf, ROW_LEN - 1),
+        Err(DecoderError::InvalidStride { stride, row_len: ROW_LEN }) if stride == ROW_LEN - 1
+    ));
+
+    let stride = ROW_LEN + 4;
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_into_strided(&mut buf, stride),
+        Err(DecoderError::BufferTooSmall { expected, actual }) if expected == stride * 479 + ROW_LEN && actual == buf.len()
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn rejects_started_decode() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::new(V1)?;
+    decoder.rows().next().unwrap()?;
+
+    let mut buf = vec![0; EXPECTED.len()];
+    assert!(matches!(
+        decoder.decode_into_strided(&mut buf, ROW_LEN),
+        Err(DecoderError::DecodeStarted { decoded_pixels: 382 })
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn read_image_strided() -> Result<(), image::ImageError> {
+    let _ = env_logger::try_init();
+
+    let stride = ROW_LEN + 16;
+    let mut buf = vec![0xaa; stride * 480];
+    QoiDecoder::new(V1)?.read_image_strided(&mut buf, stride)?;
+    check_strided(&buf, stride);
+
+    assert!(matches!(
+        QoiDecoder::new(V1)?.read_image_strided(&mut buf, 8),
+        Err(image::ImageError::Parameter(_))
+    ));
+
+    Ok(())
+}
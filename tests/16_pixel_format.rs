@@ -0,0 +1,126 @@
+use qoi::{
+    self, ColorSpace, DecoderError, DecoderLimits, FormatVersion, PixelFormat, QoiDecoder, QoiEncoder, QoiPushDecoder
+};
+
+mod common;
+use common::compare_bytes;
+
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+fn decode_as(format: PixelFormat) -> Result<Vec<u8>, DecoderError> {
+    let mut decoder = QoiDecoder::builder().output_format(format).build(V1)?;
+    assert_eq!(decoder.output_format(), format);
+
+    let mut decoded = vec![0; 382 * 480 * format.bytes_per_pixel() as usize];
+    decoder.decode_into_strided(&mut decoded, 382 * format.bytes_per_pixel() as usize)?;
+    Ok(decoded)
+}
+
+fn convert(f: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+    EXPECTED.chunks_exact(4).flat_map(f).collect()
+}
+
+fn premultiply(channel: u8, alpha: u8) -> u8 {
+    ((channel as f32 * alpha as f32 / 255.0).round()) as u8
+}
+
+#[test]
+fn swizzles() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    compare_bytes(&decode_as(PixelFormat::Rgba)?, EXPECTED);
+    compare_bytes(&decode_as(PixelFormat::Rgb)?, &convert(|p| vec![p[0], p[1], p[2]]));
+    compare_bytes(&decode_as(PixelFormat::Bgr)?, &convert(|p| vec![p[2], p[1], p[0]]));
+    compare_bytes(&decode_as(PixelFormat::Bgra)?, &convert(|p| vec![p[2], p[1], p[0], p[3]]));
+    compare_bytes(&decode_as(PixelFormat::Argb)?, &convert(|p| vec![p[3], p[0], p[1], p[2]]));
+
+    Ok(())
+}
+
+#[test]
+fn packed_and_premultiplied() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    compare_bytes(&decode_as(PixelFormat::Rgb565)?, &convert(|p| {
+        let packed = (p[0] as u16 >> 3) << 11 | (p[1] as u16 >> 2) << 5 | p[2] as u16 >> 3;
+        packed.to_le_bytes().to_vec()
+    }));
+    compare_bytes(&decode_as(PixelFormat::RgbaPremultiplied)?, &convert(|p| {
+        vec![premultiply(p[0], p[3]), premultiply(p[1], p[3]), premultiply(p[2], p[3]), p[3]]
+    }));
+    compare_bytes(&decode_as(PixelFormat::BgraPremultiplied)?, &convert(|p| {
+        vec![premultiply(p[2], p[3]), premultiply(p[1], p[3]), premultiply(p[0], p[3]), p[3]]
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn expands_rgb() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let rgb = [10, 20, 30, 40, 50, 60];
+    for version in [FormatVersion::Draft, FormatVersion::V1] {
+        let mut encoded = vec![];
+        QoiEncoder::new_with_version(&mut encoded, version).encode(&rgb, 2, 1, 3, ColorSpace::Srgb).unwrap();
+
+        let mut decoder = QoiDecoder::builder().output_format(PixelFormat::Bgra).build(&encoded[..])?;
+        let mut decoded = [0; 8];
+        assert_eq!(decoder.decode(&mut decoded)?, 8);
+        assert_eq!(decoded, [30, 20, 10, 255, 60, 50, 40, 255]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn push_decoder_output_format() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::builder().output_format(PixelFormat::Bgra).build_push();
+    let mut decoded = vec![];
+    decoder.feed(V1, |_, row| decoded.extend_from_slice(row))?;
+    decoder.finish(|_, _| {})?;
+    compare_bytes(&decoded, &decode_as(PixelFormat::Bgra)?);
+
+    let mut decoder = QoiPushDecoder::new();
+    let mut decoded = vec![];
+    decoder.feed(V1, |_, row| decoded.extend_from_slice(row))?;
+    compare_bytes(&decoded, EXPECTED);
+
+    Ok(())
+}
+
+#[test]
+fn limits_use_output_size() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let limits = DecoderLimits { max_output_bytes: 382 * 480 * 2, ..DecoderLimits::default() };
+    assert!(QoiDecoder::builder().limits(limits).build(V1).is_err());
+    QoiDecoder::builder().limits(limits).output_format(PixelFormat::Rgb565).build(V1)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn image_refuses_unrepresentable_formats() -> Result<(), image::ImageError> {
+    use image::{ColorType, ImageDecoder};
+
+    let _ = env_logger::try_init();
+
+    let decoder = QoiDecoder::builder().output_format(PixelFormat::Bgra).build(V1)?;
+    assert_eq!(decoder.color_type(), ColorType::Bgra8);
+    let mut decoded = vec![0; decoder.total_bytes() as usize];
+    decoder.read_image(&mut decoded)?;
+    compare_bytes(&decoded, &decode_as(PixelFormat::Bgra)?);
+
+    for format in [PixelFormat::Argb, PixelFormat::Rgb565, PixelFormat::RgbaPremultiplied, PixelFormat::BgraPremultiplied] {
+        let decoder = QoiDecoder::builder().output_format(format).build(V1)?;
+        let mut decoded = vec![0; 382 * 480 * 4];
+        assert!(matches!(decoder.read_image(&mut decoded), Err(image::ImageError::Unsupported(_))));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use qoi::{self, ColorSpace, EncoderError, FormatVersion, PixelFormat, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+
+fn encode(buf: &[u8], format: Option<PixelFormat>, channels: u8, version: FormatVersion) -> Result<Vec<u8>, EncoderError> {
+    let mut encoded = vec![];
+    let mut builder = QoiEncoder::builder().version(version);
+    if let Some(format) = format {
+        builder = builder.input_format(format);
+    }
+    builder.build(&mut encoded).encode(buf, 382, 480, channels, ColorSpace::Srgb)?;
+    Ok(encoded)
+}
+
+fn convert(f: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+    INITIAL.chunks_exact(4).flat_map(f).collect()
+}
+
+#[test]
+fn swizzled_input() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let bgra = convert(|p| vec![p[2], p[1], p[0], p[3]]);
+    let argb = convert(|p| vec![p[3], p[0], p[1], p[2]]);
+    let bgr = convert(|p| vec![p[2], p[1], p[0]]);
+    let rgb = convert(|p| vec![p[0], p[1], p[2]]);
+
+    for version in [FormatVersion::Draft, FormatVersion::V1] {
+        let rgba_encoded = encode(INITIAL, None, 4, version)?;
+        compare_bytes(&encode(&bgra, Some(PixelFormat::Bgra), 4, version)?, &rgba_encoded);
+        compare_bytes(&encode(&argb, Some(PixelFormat::Argb), 4, version)?, &rgba_encoded);
+
+        let rgb_encoded = encode(&rgb, None, 3, version)?;
+        compare_bytes(&encode(&bgr, Some(PixelFormat::Bgr), 3, version)?, &rgb_encoded);
+        compare_bytes(&encode(&bgra, Some(PixelFormat::Bgra), 3, version)?, &rgb_encoded);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn packed_input() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let packed = INITIAL.chunks_exact(4)
+        .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+        .collect::<Vec<_>>();
+
+    for version in [FormatVersion::Draft, FormatVersion::V1] {
+        let mut encoded = vec![];
+        QoiEncoder::new_with_version(&mut encoded, version).encode_packed(&packed, 382, 480, 4, ColorSpace::Srgb)?;
+        compare_bytes(&encoded, &encode(INITIAL, None, 4, version)?);
+
+        let mut encoded = vec![];
+        QoiEncoder::new_with_version(&mut encoded, version).encode_packed(&packed, 382, 480, 3, ColorSpace::Srgb)?;
+        compare_bytes(&encoded, &encode(INITIAL, Some(PixelFormat::Rgba), 3, version)?);
+    }
+
+    Ok(())
+}
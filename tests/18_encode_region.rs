@@ -0,0 +1,89 @@
+use qoi::{self, ColorSpace, EncoderError, FormatVersion, PixelFormat, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+const EXPECTED: &[u8] = include_bytes!("./image_v1.qoi");
+
+const ROW_LEN: usize = 382 * 4;
+
+#[test]
+fn encode_embedded_image() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let stride = 400 * 4 + 8;
+    let mut canvas = vec![0x55; stride * 490];
+    for (y, row) in INITIAL.chunks_exact(ROW_LEN).enumerate() {
+        let start = (y + 5) * stride + 10 * 4;
+        canvas[start..start + ROW_LEN].copy_from_slice(row);
+    }
+
+    let mut encoded = vec![];
+    QoiEncoder::new_with_version(&mut encoded, FormatVersion::V1)
+        .encode_region(&canvas, stride, (10, 5), (382, 480), 4, ColorSpace::SrgbLinearAlpha)?;
+    compare_bytes(&encoded, EXPECTED);
+
+    Ok(())
+}
+
+#[test]
+fn encode_tile() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let (x, y, width, height) = (100, 50, 64, 32);
+    let tile = INITIAL.chunks_exact(ROW_LEN).skip(y).take(height)
+        .flat_map(|row| row[x * 4..(x + width) * 4].chunks_exact(4))
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+        .collect::<Vec<_>>();
+
+    let mut encoded = vec![];
+    QoiEncoder::builder()
+        .input_format(PixelFormat::Rgba)
+        .build(&mut encoded)
+        .encode_region(INITIAL, ROW_LEN, (x as u32, y as u32), (width as u32, height as u32), 3, ColorSpace::Srgb)?;
+
+    let mut swizzled = vec![];
+    QoiEncoder::builder()
+        .input_format(PixelFormat::Bgr)
+        .build(&mut swizzled)
+        .encode(&tile, width as u32, height as u32, 3, ColorSpace::Srgb)?;
+
+    compare_bytes(&encoded, &swizzled);
+
+    Ok(())
+}
+
+#[test]
+fn rejects_regions_outside_the_buffer() {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    assert!(matches!(
+        QoiEncoder::new(&mut encoded).encode_region(INITIAL, ROW_LEN, (1, 0), (382, 480), 4, ColorSpace::Srgb),
+        Err(EncoderError::InvalidStride { stride: ROW_LEN, row_len }) if row_len == ROW_LEN + 4
+    ));
+    assert!(matches!(
+        QoiEncoder::new(&mut encoded).encode_region(INITIAL, ROW_LEN, (0, 1), (382, 480), 4, ColorSpace::Srgb),
+        Err(EncoderError::BufferTooSmall { expected, .. }) if expected == INITIAL.len() + ROW_LEN
+    ));
+    assert!(encoded.is_empty());
+}
+
+#[test]
+fn rejects_huge_regions() {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    assert!(matches!(
+        QoiEncoder::new(&mut encoded)
+            .encode_region(INITIAL, ROW_LEN, (u32::MAX, 0), (u32::MAX, 1), 4, ColorSpace::Srgb),
+        Err(EncoderError::InvalidStride { stride: ROW_LEN, .. })
+    ));
+    assert!(matches!(
+        QoiEncoder::new(&mut encoded)
+            .encode_region(INITIAL, usize::MAX, (u32::MAX, u32::MAX), (u32::MAX, u32::MAX), 4, ColorSpace::Srgb),
+        Err(EncoderError::BufferTooSmall { expected: usize::MAX, .. })
+    ));
+    assert!(encoded.is_empty());
+}
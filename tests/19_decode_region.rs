@@ -0,0 +1,93 @@
+use qoi::{self, DecoderError, PixelFormat, QoiDecoder};
+
+mod common;
+use common::compare_bytes;
+
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+const ROW_LEN: usize = 382 * 4;
+
+fn crop(x: usize, y: usize, width: usize, height: usize) -> Vec<u8> {
+    EXPECTED.chunks_exact(ROW_LEN).skip(y).take(height)
+        .flat_map(|row| row[x * 4..(x + width) * 4].iter().copied())
+        .collect()
+}
+
+#[test]
+fn decode_region() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    for (x, y, width, height) in [(0, 0, 382, 480), (100, 50, 64, 32), (381, 479, 1, 1), (0, 200, 382, 1)] {
+        let mut buf = vec![0; width * height * 4];
+        QoiDecoder::new(V1)?.decode_region(x as u32, y as u32, width as u32, height as u32, &mut buf)?;
+        compare_bytes(&buf, &crop(x, y, width, height));
+    }
+
+    let mut buf = vec![0; 10 * 10 * 3];
+    QoiDecoder::builder().output_format(PixelFormat::Rgb).build(V1)?.decode_region(5, 5, 10, 10, &mut buf)?;
+    let expected = crop(5, 5, 10, 10).chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect::<Vec<_>>();
+    compare_bytes(&buf, &expected);
+
+    Ok(())
+}
+
+#[test]
+fn stops_after_last_row() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut reader = V1;
+    let mut decoder = QoiDecoder::new(&mut reader)?;
+    let mut buf = vec![0; 382 * 4 * 10];
+    decoder.decode_region(0, 0, 382, 10, &mut buf)?;
+    compare_bytes(&buf, &EXPECTED[..buf.len()]);
+    drop(decoder);
+
+    // Only the chunks for the first rows, plus whatever the version probe held back, have been consumed.
+    assert!(reader.len() > V1.len() / 2);
+
+    Ok(())
+}
+
+#[test]
+fn rejects_bad_regions() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut buf = vec![0; 382 * 480 * 4];
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_region(300, 0, 83, 1, &mut buf),
+        Err(DecoderError::InvalidRegion { .. })
+    ));
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_region(0, 0, 10, 10, &mut buf[..399]),
+        Err(DecoderError::BufferTooSmall { expected: 400, actual: 399 })
+    ));
+
+    let mut decoder = QoiDecoder::new(V1)?;
+    assert_eq!(decoder.decode(&mut buf[..ROW_LEN * 2])?, ROW_LEN * 2);
+    assert!(matches!(decoder.decode_region(0, 1, 1, 1, &mut buf), Err(DecoderError::InvalidRegion { .. })));
+    decoder.decode_region(0, 2, 1, 1, &mut buf)?;
+    assert_eq!(&buf[..4], &EXPECTED[ROW_LEN * 2..ROW_LEN * 2 + 4]);
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn read_rect() -> Result<(), image::ImageError> {
+    use image::ImageDecoderExt;
+
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::new(V1)?;
+    let mut buf = vec![0; 16 * 16 * 4];
+    decoder.read_rect(200, 300, 16, 16, &mut buf)?;
+    compare_bytes(&buf, &crop(200, 300, 16, 16));
+
+    // Later rectangles can still be read from the same decoder, earlier ones can't.
+    decoder.read_rect_with_progress(0, 400, 8, 2, &mut buf, |_| {})?;
+    compare_bytes(&buf[..8 * 2 * 4], &crop(0, 400, 8, 2));
+    assert!(matches!(decoder.read_rect(0, 0, 1, 1, &mut buf), Err(image::ImageError::Parameter(_))));
+
+    Ok(())
+}
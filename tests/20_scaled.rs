@@ -0,0 +1,106 @@
+use qoi::{self, ColorSpace, DecoderError, PixelFormat, QoiDecoder, QoiEncoder};
+
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+
+// A 5x3 gray ramp, 10 per column and 100 per row, so box averages are easy to work out by hand.
+fn ramp() -> Vec<u8> {
+    let pixels = (0..3).flat_map(|y| (0..5).flat_map(move |x| [10 * x + 100 * y; 3])).collect::<Vec<u8>>();
+    let mut encoded = vec![];
+    QoiEncoder::new(&mut encoded).encode(&pixels, 5, 3, 3, ColorSpace::Srgb).unwrap();
+    encoded
+}
+
+fn gray(values: &[u8]) -> Vec<u8> {
+    values.iter().flat_map(|&value| [value, value, value, 255]).collect()
+}
+
+#[test]
+fn power_of_two_scales() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let encoded = ramp();
+    for (denominator, dimensions, expected) in [
+        (1, (5, 3), gray(&[0, 10, 20, 30, 40, 100, 110, 120, 130, 140, 200, 210, 220, 230, 240])),
+        // Columns {0, 1}, {2, 3}, {4} and rows {0, 1}, {2}.
+        (2, (3, 2), gray(&[55, 75, 90, 205, 225, 240])),
+        // Columns {0..3}, {4} and a single row.
+        (4, (2, 1), gray(&[115, 140])),
+        (8, (1, 1), gray(&[120]))
+    ] {
+        let mut decoder = QoiDecoder::builder().output_format(PixelFormat::Rgba).build(&encoded[..])?;
+        assert_eq!(decoder.scaled_dimensions(denominator), Some(dimensions));
+
+        let mut buf = vec![0; dimensions.0 as usize * dimensions.1 as usize * 4];
+        decoder.decode_scaled_by(denominator, &mut buf)?;
+        assert_eq!(buf, expected, "1/{}", denominator);
+    }
+
+    let decoder = QoiDecoder::new(V1)?;
+    assert_eq!(decoder.scaled_dimensions(4), Some((96, 120)));
+    assert_eq!(decoder.scaled_dimensions(0), None);
+
+    Ok(())
+}
+
+#[test]
+fn arbitrary_scale() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    // Columns {0..2}, {3, 4} and rows {0, 1}, {2}.
+    let mut buf = [0; 2 * 2 * 3];
+    QoiDecoder::builder().output_format(PixelFormat::Bgr).build(&ramp()[..])?.decode_scaled(2, 2, &mut buf)?;
+    assert_eq!(buf, [60, 60, 60, 85, 85, 85, 210, 210, 210, 235, 235, 235]);
+
+    Ok(())
+}
+
+#[test]
+fn transparent_pixels_dont_bleed() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    QoiEncoder::new(&mut encoded).encode(&[255, 0, 0, 0, 0, 0, 255, 255], 2, 1, 4, ColorSpace::Srgb).unwrap();
+
+    let mut buf = [0; 4];
+    QoiDecoder::new(&encoded[..])?.decode_scaled(1, 1, &mut buf)?;
+    assert_eq!(buf, [0, 0, 255, 128]);
+
+    Ok(())
+}
+
+#[test]
+fn rejects_upscaling() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut buf = vec![0; 383 * 480 * 4];
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_scaled(383, 480, &mut buf),
+        Err(DecoderError::InvalidScale { width: 383, height: 480 })
+    ));
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_scaled(0, 10, &mut buf),
+        Err(DecoderError::InvalidScale { .. })
+    ));
+    assert!(matches!(
+        QoiDecoder::new(V1)?.decode_scaled_by(0, &mut buf),
+        Err(DecoderError::InvalidScale { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn rejects_started_decode() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::new(V1)?;
+    decoder.decode(&mut [0; 10 * 4])?;
+
+    let mut buf = vec![0; 191 * 240 * 4];
+    assert!(matches!(
+        decoder.decode_scaled_by(2, &mut buf),
+        Err(DecoderError::DecodeStarted { decoded_pixels: 10 })
+    ));
+
+    Ok(())
+}
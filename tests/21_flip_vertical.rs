@@ -0,0 +1,123 @@
+use qoi::{self, ColorSpace, DecoderError, EncoderError, FormatVersion, QoiDecoder, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+
+const ROW_LEN: usize = 382 * 4;
+
+fn flipped(raw: &[u8], row_len: usize) -> Vec<u8> {
+    raw.chunks_exact(row_len).rev().flatten().copied().collect()
+}
+
+#[test]
+fn flipped_encode() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let bottom_up = flipped(INITIAL, ROW_LEN);
+
+    let mut encoded = vec![];
+    QoiEncoder::builder()
+        .version(FormatVersion::V1)
+        .flip_vertical(true)
+        .build(&mut encoded)
+        .encode(&bottom_up, 382, 480, 4, ColorSpace::SrgbLinearAlpha)?;
+    compare_bytes(&encoded, V1);
+
+    let packed = bottom_up.chunks_exact(4)
+        .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+        .collect::<Vec<_>>();
+    let mut encoded = vec![];
+    QoiEncoder::builder()
+        .version(FormatVersion::V1)
+        .flip_vertical(true)
+        .build(&mut encoded)
+        .encode_packed(&packed, 382, 480, 4, ColorSpace::SrgbLinearAlpha)?;
+    compare_bytes(&encoded, V1);
+
+    Ok(())
+}
+
+#[test]
+fn flipped_decode() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let bottom_up = flipped(INITIAL, ROW_LEN);
+
+    let mut buf = vec![0; INITIAL.len()];
+    QoiDecoder::builder().flip_vertical(true).build(V1)?.decode_into_strided(&mut buf, ROW_LEN)?;
+    compare_bytes(&buf, &bottom_up);
+
+    let mut buf = vec![0; INITIAL.len()];
+    let mut decoder = QoiDecoder::builder().flip_vertical(true).build(V1)?;
+    assert_eq!(decoder.decode(&mut buf)?, INITIAL.len());
+    assert_eq!(decoder.decode(&mut buf)?, 0);
+    compare_bytes(&buf, &bottom_up);
+
+    let region = INITIAL.chunks_exact(ROW_LEN).skip(10).take(20)
+        .flat_map(|row| row[40..80].iter().copied())
+        .collect::<Vec<_>>();
+    let mut buf = vec![0; 10 * 20 * 4];
+    QoiDecoder::builder().flip_vertical(true).build(V1)?.decode_region(10, 10, 10, 20, &mut buf)?;
+    compare_bytes(&buf, &flipped(&region, 40));
+
+    let mut unflipped = vec![0; 191 * 240 * 4];
+    QoiDecoder::new(V1)?.decode_scaled(191, 240, &mut unflipped)?;
+    let mut buf = vec![0; unflipped.len()];
+    QoiDecoder::builder().flip_vertical(true).build(V1)?.decode_scaled(191, 240, &mut buf)?;
+    compare_bytes(&buf, &flipped(&unflipped, 191 * 4));
+
+    let mut decoder = QoiDecoder::builder().flip_vertical(true).build_push();
+    let mut buf = vec![0; INITIAL.len()];
+    decoder.feed(V1, |y, row| buf[y as usize * ROW_LEN..(y as usize + 1) * ROW_LEN].copy_from_slice(row))?;
+    compare_bytes(&buf, &bottom_up);
+
+    Ok(())
+}
+
+#[test]
+fn flipped_streaming_is_refused() -> Result<(), DecoderError> {
+    use std::io::Read;
+
+    let _ = env_logger::try_init();
+
+    let builder = QoiDecoder::builder().flip_vertical(true);
+
+    let mut buf = vec![0; ROW_LEN];
+    assert!(matches!(builder.build(V1)?.decode(&mut buf), Err(DecoderError::FlipUnsupported)));
+
+    let mut decoder = builder.build(V1)?;
+    let mut rows = decoder.rows();
+    assert!(matches!(rows.next(), Some(Err(DecoderError::FlipUnsupported))));
+    assert!(rows.next().is_none());
+
+    let mut decoder = builder.build(V1)?;
+    let pixels = decoder.pixels().collect::<Vec<_>>();
+    assert!(matches!(pixels[..], [Err(DecoderError::FlipUnsupported)]));
+
+    let error = builder.build(V1)?.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn flipped_read_image() -> Result<(), image::ImageError> {
+    use image::ImageDecoder;
+
+    let _ = env_logger::try_init();
+
+    let mut buf = vec![0; INITIAL.len()];
+    QoiDecoder::builder().flip_vertical(true).build(V1)?.read_image(&mut buf)?;
+    compare_bytes(&buf, &flipped(INITIAL, ROW_LEN));
+
+    assert!(matches!(
+        QoiDecoder::builder().flip_vertical(true).build(V1)?.into_reader(),
+        Err(image::ImageError::Unsupported(_))
+    ));
+
+    Ok(())
+}
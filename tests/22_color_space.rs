@@ -0,0 +1,103 @@
+use qoi::{self, ColorSpace, DecoderError, FormatVersion, QoiDecoder, QoiEncoder};
+
+fn srgb_to_linear(value: u8) -> u8 {
+    let c = value as f64 / 255.0;
+    let linear = if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+    (linear * 255.0).round() as u8
+}
+
+fn linear_to_srgb(value: u8) -> u8 {
+    let l = value as f64 / 255.0;
+    let srgb = if l <= 0.0031308 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 };
+    (srgb * 255.0).round() as u8
+}
+
+// Every value in every channel, with alpha running backwards so it differs from the colors.
+fn ramp() -> Vec<u8> {
+    (0..=255u8).flat_map(|value| [value, value, value, 255 - value]).collect()
+}
+
+fn decode(pixels: &[u8], stored: ColorSpace, version: FormatVersion, target: ColorSpace) -> Result<(ColorSpace, Vec<u8>), DecoderError> {
+    let mut encoded = vec![];
+    QoiEncoder::new_with_version(&mut encoded, version).encode(pixels, 256, 1, 4, stored).unwrap();
+
+    let mut decoder = QoiDecoder::builder().target_color_space(target).build(&encoded[..])?;
+    let mut decoded = vec![0; pixels.len()];
+    decoder.decode(&mut decoded)?;
+    Ok((decoder.color_space(), decoded))
+}
+
+#[test]
+fn exact_tables() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let (color_space, decoded) = decode(&ramp(), ColorSpace::SrgbLinearAlpha, FormatVersion::Draft, ColorSpace::Linear)?;
+    assert_eq!(color_space, ColorSpace::Linear);
+    for (value, pixel) in decoded.chunks_exact(4).enumerate() {
+        let linear = srgb_to_linear(value as u8);
+        assert_eq!(pixel, [linear, linear, linear, 255 - value as u8]);
+    }
+
+    let (_, decoded) = decode(&ramp(), ColorSpace::Linear, FormatVersion::V1, ColorSpace::SrgbLinearAlpha)?;
+    for (value, pixel) in decoded.chunks_exact(4).enumerate() {
+        let srgb = linear_to_srgb(value as u8);
+        assert_eq!(pixel, [srgb, srgb, srgb, 255 - value as u8]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn per_channel_flags() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let (_, decoded) = decode(&ramp(), ColorSpace::Custom(true, false, true, false), FormatVersion::Draft, ColorSpace::Srgb)?;
+    for (value, pixel) in decoded.chunks_exact(4).enumerate() {
+        let value = value as u8;
+        assert_eq!(pixel, [linear_to_srgb(value), value, linear_to_srgb(value), 255 - value]);
+    }
+
+    let (_, decoded) = decode(&ramp(), ColorSpace::Srgb, FormatVersion::Draft, ColorSpace::Custom(false, true, false, true))?;
+    for (value, pixel) in decoded.chunks_exact(4).enumerate() {
+        let value = value as u8;
+        assert_eq!(pixel, [value, srgb_to_linear(value), value, srgb_to_linear(255 - value)]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn unknown_color_space_is_left_alone() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let (color_space, decoded) = decode(&ramp(), ColorSpace::Unknown(0x20), FormatVersion::V1, ColorSpace::Linear)?;
+    assert_eq!(color_space, ColorSpace::Unknown(0x20));
+    assert_eq!(decoded, ramp());
+
+    Ok(())
+}
+
+#[test]
+fn inverse_on_encode() {
+    let _ = env_logger::try_init();
+
+    let linear = ramp();
+    let srgb = linear.chunks_exact(4)
+        .flat_map(|p| [linear_to_srgb(p[0]), linear_to_srgb(p[1]), linear_to_srgb(p[2]), p[3]])
+        .collect::<Vec<_>>();
+
+    for version in [FormatVersion::Draft, FormatVersion::V1] {
+        let mut expected = vec![];
+        QoiEncoder::new_with_version(&mut expected, version)
+            .encode(&srgb, 256, 1, 4, ColorSpace::SrgbLinearAlpha).unwrap();
+
+        let mut encoded = vec![];
+        QoiEncoder::builder()
+            .version(version)
+            .input_color_space(ColorSpace::Linear)
+            .build(&mut encoded)
+            .encode(&linear, 256, 1, 4, ColorSpace::SrgbLinearAlpha).unwrap();
+
+        assert_eq!(encoded, expected);
+    }
+}
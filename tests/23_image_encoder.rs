@@ -0,0 +1,116 @@
+#![cfg(feature = "image")]
+
+use image::{ColorType, ImageEncoder, ImageResult};
+
+use qoi::{self, ColorSpace, FormatVersion, QoiDecoder, QoiEncoder, QoiEncoderBuilder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+
+fn write(buf: &[u8], color_type: ColorType, configure: impl FnOnce(QoiEncoderBuilder) -> QoiEncoderBuilder) -> ImageResult<Vec<u8>> {
+    let mut encoded = vec![];
+    configure(QoiEncoder::builder().version(FormatVersion::V1)).build(&mut encoded).write_image(buf, 382, 480, color_type)?;
+    Ok(encoded)
+}
+
+fn encode(buf: &[u8], channels: u8) -> Vec<u8> {
+    let mut encoded = vec![];
+    QoiEncoder::new_with_version(&mut encoded, FormatVersion::V1).encode(buf, 382, 480, channels, ColorSpace::Srgb).unwrap();
+    encoded
+}
+
+fn convert<const N: usize>(f: impl Fn(&[u8]) -> [u8; N]) -> Vec<u8> {
+    INITIAL.chunks_exact(4).flat_map(f).collect()
+}
+
+#[test]
+fn eight_bit_types() -> ImageResult<()> {
+    let _ = env_logger::try_init();
+
+    compare_bytes(&write(INITIAL, ColorType::Rgba8, |e| e)?, &encode(INITIAL, 4));
+    compare_bytes(&write(&convert(|p| [p[2], p[1], p[0], p[3]]), ColorType::Bgra8, |e| e)?, &encode(INITIAL, 4));
+
+    let rgb = convert(|p| [p[0], p[1], p[2]]);
+    compare_bytes(&write(&convert(|p| [p[2], p[1], p[0]]), ColorType::Bgr8, |e| e)?, &encode(&rgb, 3));
+    compare_bytes(&write(INITIAL, ColorType::Rgba8, |e| e.channels(3))?, &encode(&rgb, 3));
+
+    let gray = convert(|p| [p[1]]);
+    compare_bytes(&write(&gray, ColorType::L8, |e| e)?, &encode(&convert(|p| [p[1], p[1], p[1]]), 3));
+    let gray_alpha = convert(|p| [p[1], p[3]]);
+    compare_bytes(&write(&gray_alpha, ColorType::La8, |e| e)?, &encode(&convert(|p| [p[1], p[1], p[1], p[3]]), 4));
+
+    Ok(())
+}
+
+#[test]
+fn sixteen_bit_types() -> ImageResult<()> {
+    let _ = env_logger::try_init();
+
+    let wide = INITIAL.iter().flat_map(|&value| (value as u16 * 257).to_ne_bytes()).collect::<Vec<_>>();
+    compare_bytes(&write(&wide, ColorType::Rgba16, |e| e)?, &encode(INITIAL, 4));
+    compare_bytes(&write(&wide, ColorType::Rgba16, |e| e.dithering(true))?, &encode(INITIAL, 4));
+
+    let wide_rgb = INITIAL.chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .flat_map(|value| (value as u16 * 257).saturating_add(100).to_ne_bytes())
+        .collect::<Vec<_>>();
+    compare_bytes(&write(&wide_rgb, ColorType::Rgb16, |e| e)?, &encode(&convert(|p| [p[0], p[1], p[2]]), 3));
+
+    Ok(())
+}
+
+#[test]
+fn dithering_stays_within_one_step() -> ImageResult<()> {
+    let _ = env_logger::try_init();
+
+    // A shallow ramp where every pixel sits between two 8-bit values.
+    let wide = (0..382 * 480).flat_map(|i: u32| {
+        let value = ((i % 382) * 64 + 128) as u16;
+        [value, value, value, 65535].into_iter().flat_map(u16::to_ne_bytes)
+    }).collect::<Vec<_>>();
+
+    let rounded = write(&wide, ColorType::Rgba16, |e| e)?;
+    let dithered = write(&wide, ColorType::Rgba16, |e| e.dithering(true))?;
+    assert_ne!(rounded, dithered);
+
+    let mut rounded_pixels = vec![0; 382 * 480 * 4];
+    QoiDecoder::new(&rounded[..])?.decode(&mut rounded_pixels)?;
+    let mut dithered_pixels = vec![0; 382 * 480 * 4];
+    QoiDecoder::new(&dithered[..])?.decode(&mut dithered_pixels)?;
+    assert!(rounded_pixels.iter().zip(dithered_pixels.iter()).all(|(&a, &b)| (a as i16 - b as i16).abs() <= 1));
+
+    Ok(())
+}
+
+#[test]
+fn color_space_and_unsupported_types() -> ImageResult<()> {
+    let _ = env_logger::try_init();
+
+    let encoded = write(INITIAL, ColorType::Rgba8, |e| e.color_space(ColorSpace::Linear))?;
+    assert_eq!(QoiDecoder::new(&encoded[..])?.color_space(), ColorSpace::Linear);
+
+    assert!(write(&[0; 382 * 480 * 2], ColorType::L16, |e| e).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn owned_writer_from_builder() -> ImageResult<()> {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    QoiEncoder::builder()
+        .version(FormatVersion::V1)
+        .color_space(ColorSpace::Linear)
+        .dithering(true)
+        .build(&mut encoded)
+        .write_image(INITIAL, 382, 480, ColorType::Rgba8)?;
+
+    let decoder = QoiDecoder::new(&encoded[..])?;
+    assert_eq!(decoder.format_version(), FormatVersion::V1);
+    assert_eq!(decoder.color_space(), ColorSpace::Linear);
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use qoi::{self, ChunkStats, ColorSpace, EncodeStats, EncoderError, FormatVersion, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+const EXPECTED: &[u8] = include_bytes!("./image.qoi");
+const EXPECTED_V1: &[u8] = include_bytes!("./image_v1.qoi");
+
+fn chunk_bytes(stats: &EncodeStats) -> u64 {
+    [stats.index, stats.run8, stats.run16, stats.diff8, stats.diff16, stats.diff24, stats.diff, stats.luma, stats.run,
+        stats.rgb, stats.rgba]
+        .iter()
+        .chain(stats.color.iter())
+        .map(|chunks| chunks.bytes)
+        .sum()
+}
+
+#[test]
+fn stats_match_output() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    for (version, color_space, expected, trailer) in [
+        (FormatVersion::Draft, ColorSpace::Srgb, EXPECTED, 4),
+        (FormatVersion::V1, ColorSpace::SrgbLinearAlpha, EXPECTED_V1, 8)
+    ] {
+        let mut encoded = vec![];
+        let stats = QoiEncoder::new_with_version(&mut encoded, version)
+            .encode(INITIAL, 382, 480, 4, color_space)?;
+        compare_bytes(&encoded, expected);
+
+        assert_eq!(stats.pixels, 382 * 480);
+        assert_eq!(stats.raw_bytes, INITIAL.len() as u64);
+        assert_eq!(stats.encoded_bytes, encoded.len() as u64);
+        assert_eq!(chunk_bytes(&stats) + 14 + trailer, encoded.len() as u64);
+        assert!(stats.compression_ratio() > 0.0 && stats.compression_ratio() < 1.0);
+        assert!(stats.index_hit_rate() > 0.0 && stats.index_hit_rate() < 1.0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dry_run_writes_nothing() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    let stats = QoiEncoder::new(&mut encoded)
+        .encode(INITIAL, 382, 480, 4, ColorSpace::Srgb)?;
+
+    let mut nothing = vec![];
+    let dry_stats = QoiEncoder::builder()
+        .dry_run(true)
+        .build(&mut nothing)
+        .encode(INITIAL, 382, 480, 4, ColorSpace::Srgb)?;
+
+    assert!(nothing.is_empty());
+    assert_eq!(stats, dry_stats);
+
+    Ok(())
+}
+
+#[test]
+fn equal_stats_ignore_run_position() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    // The same chunks, with the run either before or after the red pixel.
+    let ends_red = [0, 0, 0, 0, 0, 0, 0, 0, 0, 100, 0, 0];
+    let ends_in_run = [100, 0, 0, 100, 0, 0, 100, 0, 0, 100, 0, 0];
+
+    for version in [FormatVersion::Draft, FormatVersion::V1] {
+        let stats = QoiEncoder::builder().version(version).dry_run(true).build(vec![])
+            .encode(&ends_red, 4, 1, 3, ColorSpace::Srgb)?;
+        let run_stats = QoiEncoder::builder().version(version).dry_run(true).build(vec![])
+            .encode(&ends_in_run, 4, 1, 3, ColorSpace::Srgb)?;
+        assert_eq!(stats, run_stats);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn chunk_breakdown() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    // A red pixel, forty black ones, then red again from the index.
+    let mut pixels = vec![100, 0, 0];
+    pixels.extend([0; 40 * 3]);
+    pixels.extend([100, 0, 0]);
+
+    let mut encoded = vec![];
+    let stats = QoiEncoder::new(&mut encoded)
+        .encode(&pixels, 42, 1, 3, ColorSpace::Srgb)?;
+
+    assert_eq!(stats.pixels, 42);
+    assert_eq!(stats.raw_bytes, 42 * 3);
+    assert_eq!(stats.longest_run, 39);
+    assert_eq!(stats.color[0b1000], ChunkStats { count: 2, bytes: 4 });
+    assert_eq!(stats.run16, ChunkStats { count: 1, bytes: 2 });
+    assert_eq!(stats.index, ChunkStats { count: 1, bytes: 1 });
+    assert_eq!(stats.chunks(), 4);
+    assert_eq!(stats.index_hit_rate(), 1.0 / 3.0);
+    assert_eq!(stats.encoded_bytes, encoded.len() as u64);
+
+    Ok(())
+}
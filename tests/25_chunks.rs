@@ -0,0 +1,86 @@
+use qoi::{self, DecoderError, EncoderError, FormatVersion, QoiChunk, QoiChunks, QoiHeader, ReadQoiChunk, WriteQoiChunk};
+
+const DRAFT: &[u8] = include_bytes!("./image.qoi");
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+
+#[test]
+fn chunks_cover_stream() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    for (encoded, version, trailer) in [(DRAFT, FormatVersion::Draft, 4), (V1, FormatVersion::V1, 8)] {
+        let mut reader = encoded;
+        let header = QoiHeader::read_from(&mut reader)?;
+
+        let mut rewritten = encoded[..QoiHeader::SIZE].to_vec();
+        let (mut offset, mut pixel) = (QoiHeader::SIZE as u64, 0);
+        for item in QoiChunks::new(reader, &header, version) {
+            let (chunk_offset, chunk_pixel, chunk) = item?;
+            assert_eq!((chunk_offset, chunk_pixel), (offset, pixel));
+            assert!(chunk.is_valid());
+
+            offset += chunk.encoded_len() as u64;
+            pixel += chunk.pixel_count() as u64;
+            rewritten.write_qoi_chunk(chunk).unwrap();
+        }
+
+        assert_eq!(pixel, header.pixel_count());
+        assert_eq!(offset as usize + trailer, encoded.len());
+        assert_eq!(&rewritten[..], &encoded[..offset as usize]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn chunks_report_truncation() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut reader = &V1[..V1.len() / 2];
+    let header = QoiHeader::read_from(&mut reader)?;
+
+    let items = QoiChunks::new(reader, &header, FormatVersion::V1).collect::<Vec<_>>();
+    let (last, chunks) = items.split_last().unwrap();
+    assert!(!chunks.is_empty() && chunks.iter().all(Result::is_ok));
+    assert!(matches!(last, Err(DecoderError::Truncated { expected_pixels: 183_360, .. })));
+
+    Ok(())
+}
+
+#[test]
+fn run16_is_raw() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    encoded.write_qoi_chunk(QoiChunk::Run16(0)).unwrap();
+    encoded.write_qoi_chunk(QoiChunk::Run16(0x1fff)).unwrap();
+    assert_eq!(encoded, [0x60, 0x00, 0x7f, 0xff]);
+
+    let mut reader = &encoded[..];
+    assert_eq!(reader.read_qoi_chunk(FormatVersion::Draft)?, QoiChunk::Run16(0));
+    assert_eq!(reader.read_qoi_chunk(FormatVersion::Draft)?, QoiChunk::Run16(0x1fff));
+    assert_eq!(QoiChunk::Run16(0).pixel_count(), 33);
+    assert_eq!(QoiChunk::Run16(0x1fff).pixel_count(), 0x2020);
+
+    Ok(())
+}
+
+#[test]
+fn writer_rejects_out_of_range_fields() {
+    let _ = env_logger::try_init();
+
+    for chunk in [
+        QoiChunk::Index(64),
+        QoiChunk::Run8(32),
+        QoiChunk::Run16(0x2000),
+        QoiChunk::Diff8(4, 0, 0),
+        QoiChunk::Diff16(0, 16, 0),
+        QoiChunk::Diff24(0, 0, 0, 32),
+        QoiChunk::Diff(0, 0, 4),
+        QoiChunk::Luma(64, 0, 0),
+        QoiChunk::Run(62)
+    ] {
+        let mut encoded = vec![];
+        assert!(matches!(encoded.write_qoi_chunk(chunk), Err(EncoderError::InvalidChunk(c)) if c == chunk));
+        assert!(encoded.is_empty());
+    }
+}
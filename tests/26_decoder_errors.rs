@@ -0,0 +1,163 @@
+use qoi::{
+    self, ColorSpace, DecodeMode, DecoderError, DecoderLimits, FormatVersion, Limit, QoiChunks, QoiDecoder, QoiEncoder,
+    QoiHeader, StreamPosition
+};
+
+const INITIAL: &[u8] = include_bytes!("./image_v1.qoi");
+
+fn decode(data: &[u8], mode: DecodeMode) -> Result<Vec<u8>, DecoderError> {
+    let mut decoder = QoiDecoder::builder()
+        .version(FormatVersion::V1)
+        .mode(mode)
+        .build(data)?;
+    let (width, height) = decoder.dimensions();
+    let mut decoded = vec![0; width as usize * height as usize * decoder.channels() as usize];
+    decoder.decode(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[test]
+fn truncated() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let cut = INITIAL.len() / 2;
+    let mut reader = INITIAL;
+    let header = QoiHeader::read_from(&mut reader)?;
+    let (offset, pixel, _) = QoiChunks::new(reader, &header, FormatVersion::V1)
+        .map(Result::unwrap)
+        .find(|(offset, _, chunk)| *offset as usize + chunk.encoded_len() > cut)
+        .unwrap();
+    let position = StreamPosition { offset, pixel, x: (pixel % 382) as u32, y: (pixel / 382) as u32 };
+
+    match decode(&INITIAL[..cut], DecodeMode::Normal) {
+        Err(DecoderError::Truncated { expected_pixels, decoded_pixels, position: at }) => {
+            assert_eq!(expected_pixels, 382 * 480);
+            assert_eq!(decoded_pixels, pixel);
+            assert_eq!(at, position);
+        },
+        result => panic!("Unexpected result {:?}", result.map(|_| ()))
+    }
+
+    let mut decoder = QoiDecoder::builder().version(FormatVersion::V1).build_push();
+    decoder.feed(&INITIAL[..cut], |_, _| {})?;
+    match decoder.finish(|_, _| {}) {
+        Err(DecoderError::Truncated { decoded_pixels, position: at, .. }) => {
+            assert_eq!(decoded_pixels, pixel);
+            assert_eq!(at, position);
+        },
+        result => panic!("Unexpected result {:?}", result)
+    }
+
+    Ok(())
+}
+
+#[test]
+fn trailer_positions() {
+    let _ = env_logger::try_init();
+
+    let end = StreamPosition { offset: INITIAL.len() as u64 - 8, pixel: 382 * 480, x: 0, y: 480 };
+
+    let mut garbled = INITIAL.to_vec();
+    *garbled.last_mut().unwrap() = 2;
+    match decode(&garbled, DecodeMode::Normal) {
+        Err(DecoderError::InvalidEndMarker { position, .. }) => assert_eq!(position, end),
+        result => panic!("Unexpected result {:?}", result.map(|_| ()))
+    }
+
+    match decode(&INITIAL[..INITIAL.len() - 3], DecodeMode::Normal) {
+        Err(DecoderError::Truncated { expected_pixels, decoded_pixels, position }) => {
+            assert_eq!(expected_pixels, decoded_pixels);
+            assert_eq!(position, end);
+        },
+        result => panic!("Unexpected result {:?}", result.map(|_| ()))
+    }
+
+    let mut trailing = INITIAL.to_vec();
+    trailing.push(0);
+    match decode(&trailing, DecodeMode::Strict) {
+        Err(DecoderError::TrailingData { position }) => assert_eq!(position.offset, INITIAL.len() as u64),
+        result => panic!("Unexpected result {:?}", result.map(|_| ()))
+    }
+}
+
+#[test]
+fn display_includes_position() {
+    let _ = env_logger::try_init();
+
+    let error = decode(&INITIAL[..INITIAL.len() - 3], DecodeMode::Normal).unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains(&format!("byte {}", INITIAL.len() - 8)), "{}", message);
+    assert!(message.contains("(0, 480)"), "{}", message);
+
+    let io_error: std::io::Error = error.into();
+    assert_eq!(io_error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn header_positions() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let mut stream = [INITIAL, INITIAL].concat();
+    stream[INITIAL.len()] = b'x';
+    let mut decoder = QoiDecoder::new(&stream[..])?;
+    match decoder.next_image() {
+        Err(DecoderError::InvalidSignature { position, .. }) =>
+            assert_eq!(position, StreamPosition { offset: INITIAL.len() as u64, ..StreamPosition::default() }),
+        result => panic!("Unexpected result {:?}", result)
+    }
+
+    let mut small = vec![];
+    QoiEncoder::new_with_version(&mut small, FormatVersion::V1).encode(&[0; 4], 1, 1, 4, ColorSpace::Srgb).unwrap();
+    let stream = [&small[..], INITIAL].concat();
+    let limits = DecoderLimits { max_width: 100, ..DecoderLimits::default() };
+    let mut decoder = QoiDecoder::builder().limits(limits).mode(DecodeMode::Lenient).build(&stream[..])?;
+    match decoder.next_image() {
+        Err(DecoderError::LimitExceeded { limit: Limit::Width, position, .. }) => assert_eq!(position.offset, small.len() as u64),
+        result => panic!("Unexpected result {:?}", result)
+    }
+
+    Ok(())
+}
+
+// Fails with a connection reset once `fail_at` bytes have been read.
+struct FailingReader<'a> {
+    data: &'a [u8],
+    fail_at: usize
+}
+
+impl std::io::Read for FailingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.fail_at == 0 {
+            return Err(std::io::ErrorKind::ConnectionReset.into());
+        }
+
+        let amt = buf.len().min(self.fail_at);
+        let read = std::io::Read::read(&mut self.data, &mut buf[..amt])?;
+        self.fail_at -= read;
+        Ok(read)
+    }
+}
+
+#[test]
+fn io_error_positions() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let cut = INITIAL.len() / 2;
+    let mut decoder = QoiDecoder::new_with_version(FailingReader { data: INITIAL, fail_at: cut }, FormatVersion::V1)?;
+    let mut decoded = vec![0; 382 * 480 * 4];
+    match decoder.decode(&mut decoded) {
+        Err(DecoderError::IoError { error, position }) => {
+            assert_eq!(error.kind(), std::io::ErrorKind::ConnectionReset);
+            assert!(position.offset <= cut as u64 && position.offset > cut as u64 - 5, "{:?}", position);
+            assert!(position.pixel > 0);
+        },
+        result => panic!("Unexpected result {:?}", result)
+    }
+
+    let mut decoder = QoiDecoder::new_with_version(FailingReader { data: INITIAL, fail_at: cut }, FormatVersion::V1)?;
+    let error: std::io::Error = decoder.decode(&mut decoded).unwrap_err().into();
+    assert_eq!(error.kind(), std::io::ErrorKind::ConnectionReset);
+    assert!(error.to_string().contains(" at byte "), "{}", error);
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use qoi::{self, ColorSpace, EncoderError, QoiEncoder};
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+
+fn encode(buf: &[u8], width: u32, height: u32, channels: u8) -> (Result<(), EncoderError>, Vec<u8>) {
+    let mut encoded = vec![];
+    let result = QoiEncoder::new(&mut encoded).encode(buf, width, height, channels, ColorSpace::Srgb).map(|_| ());
+    (result, encoded)
+}
+
+#[test]
+fn buffer_size_mismatch() {
+    let _ = env_logger::try_init();
+
+    let (result, encoded) = encode(&INITIAL[..INITIAL.len() - 4], 382, 480, 4);
+    assert!(matches!(result, Err(EncoderError::BufferSizeMismatch { expected, actual })
+        if expected == INITIAL.len() && actual == INITIAL.len() - 4));
+    assert!(encoded.is_empty());
+
+    let mut long = INITIAL.to_vec();
+    long.push(0);
+    let (result, encoded) = encode(&long, 382, 480, 4);
+    assert!(matches!(result, Err(EncoderError::BufferSizeMismatch { .. })));
+    assert!(encoded.is_empty());
+
+    let packed = vec![0u32; 382 * 480 - 1];
+    let mut encoded = vec![];
+    let result = QoiEncoder::builder()
+        .flip_vertical(true)
+        .build(&mut encoded)
+        .encode_packed(&packed, 382, 480, 4, ColorSpace::Srgb);
+    assert!(matches!(result, Err(EncoderError::BufferSizeMismatch { expected, .. }) if expected == 382 * 480 * 4));
+    assert!(encoded.is_empty());
+}
+
+#[test]
+fn invalid_channels() {
+    let _ = env_logger::try_init();
+
+    for channels in [0, 1, 2, 5] {
+        let (result, encoded) = encode(INITIAL, 382, 480, channels);
+        assert!(matches!(result, Err(EncoderError::InvalidChannelCount(c)) if c == channels));
+        assert!(encoded.is_empty());
+    }
+}
+
+#[test]
+fn invalid_dimensions() {
+    let _ = env_logger::try_init();
+
+    for (width, height) in [(0, 480), (382, 0), (0, 0)] {
+        let (result, encoded) = encode(&[], width, height, 4);
+        assert!(matches!(result, Err(EncoderError::InvalidDimensions { width: w, height: h }) if (w, h) == (width, height)));
+        assert!(encoded.is_empty());
+    }
+
+    let mut encoded = vec![];
+    let result = QoiEncoder::new(&mut encoded)
+        .encode_region(INITIAL, 382 * 4, (0, 0), (0, 10), 4, ColorSpace::Srgb);
+    assert!(matches!(result, Err(EncoderError::InvalidDimensions { .. })));
+    assert!(encoded.is_empty());
+}
@@ -0,0 +1,103 @@
+use qoi::{self, ColorSpace, EncoderError, FormatVersion, PixelFormat, QoiEncoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image.raw");
+const EXPECTED: &[u8] = include_bytes!("./image.qoi");
+const EXPECTED_V1: &[u8] = include_bytes!("./image_v1.qoi");
+
+struct Sink {
+    encoder: QoiEncoder<Vec<u8>>
+}
+
+fn sink(version: FormatVersion, color_space: ColorSpace) -> Sink {
+    Sink {
+        encoder: QoiEncoder::builder()
+            .version(version)
+            .channels(4)
+            .color_space(color_space)
+            .build(Vec::new())
+    }
+}
+
+#[test]
+fn owned_writer() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let mut sink = sink(FormatVersion::V1, ColorSpace::SrgbLinearAlpha);
+    assert!(sink.encoder.get_ref().is_empty());
+    sink.encoder.encode_image(INITIAL, 382, 480)?;
+    compare_bytes(&sink.encoder.into_inner(), EXPECTED_V1);
+
+    let mut encoder = QoiEncoder::new(Vec::new());
+    encoder.encode(INITIAL, 382, 480, 4, ColorSpace::Srgb)?;
+    compare_bytes(encoder.get_ref(), EXPECTED);
+
+    encoder.get_mut().clear();
+    encoder.encode(INITIAL, 382, 480, 4, ColorSpace::Srgb)?;
+    compare_bytes(&encoder.into_inner(), EXPECTED);
+
+    Ok(())
+}
+
+#[test]
+fn builder_options() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let bgr = INITIAL.chunks_exact(4).flat_map(|pixel| [pixel[2], pixel[1], pixel[0]]).collect::<Vec<_>>();
+    let rgb = INITIAL.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect::<Vec<_>>();
+
+    let mut expected = vec![];
+    QoiEncoder::new(&mut expected).encode(&rgb, 382, 480, 3, ColorSpace::Linear)?;
+
+    let mut encoder = QoiEncoder::builder()
+        .channels(3)
+        .color_space(ColorSpace::Linear)
+        .input_format(PixelFormat::Bgr)
+        .build(Vec::new());
+    let stats = encoder.encode_image(&bgr, 382, 480)?;
+    compare_bytes(encoder.get_ref(), &expected);
+
+    let mut dry = QoiEncoder::builder()
+        .channels(3)
+        .color_space(ColorSpace::Linear)
+        .dry_run(true)
+        .build(Vec::new());
+    assert_eq!(dry.encode_image(&rgb, 382, 480)?, stats);
+    assert!(dry.into_inner().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn positional_arguments_match_builder() -> Result<(), EncoderError> {
+    let _ = env_logger::try_init();
+
+    let mut encoded = vec![];
+    let mut encoder = QoiEncoder::builder()
+        .channels(4)
+        .color_space(ColorSpace::Linear)
+        .build(&mut encoded);
+
+    assert!(matches!(
+        encoder.encode(INITIAL, 382, 480, 3, ColorSpace::Linear),
+        Err(EncoderError::ChannelsMismatch { expected: 4, actual: 3 })
+    ));
+    assert!(matches!(
+        encoder.encode_region(INITIAL, 382 * 4, (0, 0), (382, 480), 4, ColorSpace::Srgb),
+        Err(EncoderError::ColorSpaceMismatch { expected: ColorSpace::Linear, actual: ColorSpace::Srgb })
+    ));
+    assert!(matches!(
+        encoder.encode_packed(&[0; 4], 2, 2, 3, ColorSpace::Linear),
+        Err(EncoderError::ChannelsMismatch { .. })
+    ));
+    assert!(encoder.get_ref().is_empty());
+
+    encoder.encode(INITIAL, 382, 480, 4, ColorSpace::Linear)?;
+    let mut expected = vec![];
+    QoiEncoder::new(&mut expected).encode(INITIAL, 382, 480, 4, ColorSpace::Linear)?;
+    compare_bytes(&encoded, &expected);
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::io::Read;
+
+use qoi::{self, DecodeMode, DecoderError, FormatVersion, QoiDecoder};
+
+mod common;
+use common::compare_bytes;
+
+const DRAFT: &[u8] = include_bytes!("./image.qoi");
+const V1: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+#[test]
+fn concatenated_images() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let stream = [DRAFT, V1, DRAFT].concat();
+    let mut decoder = QoiDecoder::new(&stream[..])?;
+
+    let mut versions = vec![];
+    loop {
+        let mut decoded = vec![0; EXPECTED.len()];
+        decoder.decode(&mut decoded)?;
+        compare_bytes(&decoded, EXPECTED);
+        versions.push(decoder.format_version());
+
+        if !decoder.next_image()? {
+            break;
+        }
+    }
+
+    assert_eq!(versions, [FormatVersion::Draft, FormatVersion::V1, FormatVersion::Draft]);
+    assert!(!decoder.next_image()?);
+
+    Ok(())
+}
+
+#[test]
+fn next_image_skips_the_rest() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let stream = [V1, V1].concat();
+    let mut decoder = QoiDecoder::builder()
+        .mode(DecodeMode::Lenient)
+        .build(&stream[..])?;
+
+    let mut first_rows = vec![0; 382 * 4 * 10];
+    decoder.decode(&mut first_rows)?;
+    assert!(decoder.next_image()?);
+
+    let mut decoded = vec![0; EXPECTED.len()];
+    decoder.decode(&mut decoded)?;
+    compare_bytes(&decoded, EXPECTED);
+    assert!(!decoder.next_image()?);
+
+    Ok(())
+}
+
+#[test]
+fn reader_recovery() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    for encoded in [DRAFT, V1] {
+        let container = [b"HEAD".as_slice(), encoded, b"TAIL".as_slice()].concat();
+        let mut reader = &container[4..];
+
+        let mut decoder = QoiDecoder::new(&mut reader)?;
+        assert_eq!(decoder.buffer().len() + decoder.get_ref().len(), container.len() - 4 - 14);
+
+        let mut decoded = vec![0; EXPECTED.len()];
+        decoder.decode(&mut decoded)?;
+        compare_bytes(&decoded, EXPECTED);
+
+        let (reader, mut rest) = decoder.into_inner();
+        reader.read_to_end(&mut rest)?;
+        assert_eq!(rest, b"TAIL");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn strict_keeps_trailing_data() -> Result<(), DecoderError> {
+    let _ = env_logger::try_init();
+
+    let container = [V1, b"TAIL".as_slice()].concat();
+    let mut decoder = QoiDecoder::builder().mode(DecodeMode::Strict).build(&container[..])?;
+    let mut decoded = vec![0; EXPECTED.len()];
+    assert!(matches!(decoder.decode(&mut decoded), Err(DecoderError::TrailingData { .. })));
+    compare_bytes(&decoded, EXPECTED);
+
+    let (reader, mut rest) = decoder.into_inner();
+    rest.extend_from_slice(reader);
+    assert_eq!(rest, b"TAIL");
+
+    let stream = [DRAFT, V1].concat();
+    let mut decoder = QoiDecoder::builder().mode(DecodeMode::Strict).build(&stream[..])?;
+    assert!(matches!(decoder.decode(&mut decoded), Err(DecoderError::TrailingData { .. })));
+    assert!(decoder.next_image()?);
+    decoder.decode(&mut decoded)?;
+    compare_bytes(&decoded, EXPECTED);
+    assert_eq!(decoder.format_version(), FormatVersion::V1);
+    assert!(!decoder.next_image()?);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::io::{self, BufRead, Read};
+
+use qoi::{self, FormatVersion, PixelFormat, QoiDecoder};
+
+mod common;
+use common::compare_bytes;
+
+const INITIAL: &[u8] = include_bytes!("./image_v1.qoi");
+const EXPECTED: &[u8] = include_bytes!("./image.raw");
+
+fn read_in(decoder: &mut impl Read, size: usize) -> io::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    let mut buf = vec![0; size];
+    loop {
+        match decoder.read(&mut buf)? {
+            0 => return Ok(decoded),
+            read => decoded.extend_from_slice(&buf[..read])
+        }
+    }
+}
+
+#[test]
+fn odd_read_sizes() -> io::Result<()> {
+    let _ = env_logger::try_init();
+
+    for size in [1, 2, 3, 5, 7, 4093] {
+        let mut decoder = QoiDecoder::new_with_version(INITIAL, FormatVersion::V1)?;
+        compare_bytes(&read_in(&mut decoder, size)?, EXPECTED);
+    }
+
+    let rgb = EXPECTED.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect::<Vec<_>>();
+    for size in [2, 4, 8] {
+        let mut decoder = QoiDecoder::builder()
+            .version(FormatVersion::V1)
+            .output_format(PixelFormat::Rgb)
+            .build(INITIAL)?;
+        compare_bytes(&read_in(&mut decoder, size)?, &rgb);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn copy_and_buf_read() -> io::Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::new(INITIAL)?;
+    let mut decoded = vec![];
+    io::copy(&mut decoder, &mut decoded)?;
+    compare_bytes(&decoded, EXPECTED);
+
+    let mut decoder = QoiDecoder::new(INITIAL)?;
+    let mut decoded = vec![];
+    loop {
+        let available = decoder.fill_buf()?;
+        if available.is_empty() {
+            break;
+        }
+
+        // Take an odd amount to leave part of a pixel behind.
+        let amt = available.len().min(11);
+        decoded.extend_from_slice(&available[..amt]);
+        decoder.consume(amt);
+    }
+    compare_bytes(&decoded, EXPECTED);
+
+    Ok(())
+}
+
+#[test]
+fn read_reports_errors() {
+    let _ = env_logger::try_init();
+
+    let mut decoder = QoiDecoder::new_with_version(&INITIAL[..INITIAL.len() / 2], FormatVersion::V1).unwrap();
+    let error = read_in(&mut decoder, 3).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}